@@ -1,70 +1,87 @@
 use crate::error::ContractError;
+use crate::migrations::migrate_from_v0_1;
 use crate::msg::{
-    ConfigResponse, Cw20HookMsg, Cw721HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
-    StakerInfoResponse, StateResponse,
+    AssetSolvency, ClaimOperatorsResponse, CollectionMetadataResponse, ConfigResponse, Cw20HookMsg,
+    Cw721HookMsg, ExecuteMsg, GuildsResponse, InstantiateMsg, MigrateMsg, MintExtension,
+    MintStatsResponse, MultiplierResponse, NftMetadata, OwnershipResponse, PauseResponse,
+    PhaseMintsResponse, PositionResponse, PositionsResponse, ProposalResponse, ProposalsResponse,
+    QueryMsg, RewardRecipientResponse, RolesResponse, SalePhasesResponse, ScheduleResponse,
+    SchedulesResponse, SolvencyResponse, StakeAtHeightResponse, StakerInfoResponse,
+    StakerRewardResponse, StakingOperator, StakingOperatorsResponse, StateResponse,
+    TokenMultiplier, TokenUriResponse, TotalStakeAtHeightResponse,
+    TotalVotingPowerAtHeightResponse, TraitMultiplier, VoteResponse, VotingPowerAtHeightResponse,
 };
 use crate::state::{
-    guild_state, remove_user_staking, store_guild_state, store_user_staking, user_staking, Config,
-    GuildState, StakerInfo, CONFIG, NINJA_GUILD, SCIENTIST_GUILD,
+    guild_schedules, guild_state, pause_state, positions, remove_user_staking, schedules,
+    stake_at_height, staker_positions, store_guild_state, store_user_staking,
+    total_stake_at_height, total_voting_power_at_height, user_staking, voting_power_at_height,
+    AssetInfo, Ballot, Collection, CollectionMetadata, Config, GuildState, LockBonus, LockExpiry,
+    LockTier, MetadataTemplate, PauseState, PendingOwner, Position, Proposal, ProposalStatus,
+    RewardState, Role, SaleKind, SalePhase, Schedule, StakeLock, StakerInfo, StakerReward,
+    TokenIdMode, VoteOption, AUTO_COMPOUND, BALLOTS, CLAIMS, CLAIM_OPERATORS, COLLECTIONS,
+    COLLECTION_METADATA, CONFIG, GUILDS, LOCK_EXPIRIES, OWNER, PAUSE, PENDING_OWNER, PHASE_MINTS,
+    PROPOSALS, PROPOSAL_COUNT, REWARD_RECIPIENTS, ROLES, SALE_PHASES, SCHEDULE_COUNT, SHUFFLED_IDS,
+    STAKING_OPERATORS, TOKEN_MULTIPLIERS, TOTAL_UNBONDING, TRAIT_MULTIPLIERS,
 };
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_json, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut,
-    Empty, Env, MessageInfo, Response, StdResult, Uint128, WasmMsg,
+    attr, from_json, to_json_binary, Addr, Attribute, Binary, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Empty, Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128,
+    WasmMsg,
 };
-use cw2::set_contract_version;
-use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
-use cw721::Cw721ReceiveMsg;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use cw721::{Cw721QueryMsg, Cw721ReceiveMsg, NftInfoResponse};
 use cw721_base::{ExecuteMsg as Cw721ExecuteMsg, MintMsg};
+use cw_controllers::ClaimsResponse;
+use cw_storage_plus::Bound;
+use cw_utils::{must_pay, Expiration};
+use semver::Version;
+use sha2::{Digest, Sha256};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:qtuamdao";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
-    env: Env,
+    mut deps: DepsMut,
+    _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    validate_denom(&msg.usdt_denom)?;
+    validate_nft_price(msg.nft_price)?;
+    if let Some(native_nft_price) = &msg.native_nft_price {
+        validate_native_nft_price(native_nft_price)?;
+    }
+    validate_voting_period(msg.voting_period)?;
+    validate_threshold(msg.quorum)?;
+    validate_threshold(msg.threshold)?;
+    validate_lock_tiers(&msg.lock_tiers)?;
+
+    OWNER.set(deps.branch(), Some(info.sender))?;
+
     CONFIG.save(
         deps.storage,
         &Config {
-            owner: info.sender,
-            scientist_nft_addr: deps.api.addr_validate(&msg.scientist_nft_addr)?,
-            ninja_nft_addr: deps.api.addr_validate(&msg.ninja_nft_addr)?,
             qtum_addr: deps.api.addr_validate(&msg.qtum_addr)?,
             xqtum_addr: deps.api.addr_validate(&msg.xqtum_addr)?,
             usdt_denom: msg.usdt_denom,
             nft_price: msg.nft_price,
-            ninja_distribution_schedule: vec![],
-            scientist_distribution_schedule: vec![],
-        },
-    )?;
-
-    NINJA_GUILD.save(
-        deps.storage,
-        &GuildState {
-            total_rewards_distributed: Uint128::zero(),
-            total_staker: 0,
-            last_distributed: env.block.time.seconds(),
-            total_stake_amount: Uint128::zero(),
-            global_reward_index: Decimal::zero(),
-        },
-    )?;
-
-    SCIENTIST_GUILD.save(
-        deps.storage,
-        &GuildState {
-            total_rewards_distributed: Uint128::zero(),
-            total_staker: 0,
-            last_distributed: env.block.time.seconds(),
-            total_stake_amount: Uint128::zero(),
-            global_reward_index: Decimal::zero(),
+            native_nft_price: msg.native_nft_price,
+            voting_period: msg.voting_period,
+            quorum: msg.quorum,
+            threshold: msg.threshold,
+            lock_tiers: msg.lock_tiers,
+            unbonding_period: msg.unbonding_period,
         },
     )?;
 
@@ -81,16 +98,205 @@ pub fn execute(
     match msg {
         ExecuteMsg::Receive(msg) => cw20_receive(deps, env, info, msg),
         ExecuteMsg::ReceiveNft(msg) => cw721_receive(deps, env, info, msg),
-        ExecuteMsg::Unlock { id } => unlock_nft(deps, env, info.sender, id),
-        ExecuteMsg::Unstake { amount } => unstake_xqtum(deps, env, info.sender, amount),
-        ExecuteMsg::Withdraw {} => withdraw_reward(deps, env, info),
+        ExecuteMsg::Unlock { nft_addr, token_id } => {
+            // unlocking pays out the pending rewards as well
+            let pause = pause_state(deps.storage)?;
+            assert_not_paused(pause.unstake || pause.withdraw)?;
+            unlock_nft(deps, env, info.sender, nft_addr, token_id)
+        }
+        ExecuteMsg::Unstake {
+            nft_addr,
+            token_id,
+            amount,
+        } => {
+            assert_not_paused(pause_state(deps.storage)?.unstake)?;
+            unstake_xqtum(deps, env, info.sender, nft_addr, token_id, amount)
+        }
+        ExecuteMsg::Mint {
+            id,
+            nft_addr,
+            proof,
+        } => {
+            assert_not_paused(pause_state(deps.storage)?.mint)?;
+
+            let denom = CONFIG
+                .load(deps.storage)?
+                .native_nft_price
+                .ok_or(ContractError::NativeMintDisabled {})?
+                .denom;
+            let paid = must_pay(&info, &denom)?;
+            purchase_nft(
+                deps,
+                env,
+                info.sender,
+                nft_addr,
+                id,
+                proof,
+                AssetInfo::Native { denom },
+                paid,
+            )
+        }
+        ExecuteMsg::Withdraw { recipient } => {
+            assert_not_paused(pause_state(deps.storage)?.withdraw)?;
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => reward_recipient(deps.storage, &info.sender)?,
+            };
+            withdraw_reward(deps, env, info.sender, recipient)
+        }
+        ExecuteMsg::WithdrawFor { staker } => {
+            assert_not_paused(pause_state(deps.storage)?.withdraw)?;
+            let staker = deps.api.addr_validate(&staker)?;
+            if !CLAIM_OPERATORS.has(deps.storage, (&staker, &info.sender)) {
+                assert_staking_operator(deps.storage, &env, &staker, &info.sender)?;
+            }
+            let recipient = reward_recipient(deps.storage, &staker)?;
+            withdraw_reward(deps, env, staker, recipient)
+        }
+        ExecuteMsg::ApproveOperator { operator, expires } => {
+            approve_operator(deps, env, info, operator, expires)
+        }
+        ExecuteMsg::RevokeOperator { operator } => revoke_operator(deps, info, operator),
+        ExecuteMsg::SetRewardRecipient { recipient } => set_reward_recipient(deps, info, recipient),
+        ExecuteMsg::ApproveClaimOperator { operator } => {
+            approve_claim_operator(deps, info, operator)
+        }
+        ExecuteMsg::RevokeClaimOperator { operator } => revoke_claim_operator(deps, info, operator),
+        // compounding claims the rewards and stakes them
+        ExecuteMsg::Compound {} => {
+            let pause = pause_state(deps.storage)?;
+            assert_not_paused(pause.stake || pause.withdraw)?;
+            compound(deps, env, info)
+        }
+        ExecuteMsg::SetAutoCompound { enabled } => set_auto_compound(deps, info, enabled),
+        ExecuteMsg::AutoCompound { staker } => {
+            let pause = pause_state(deps.storage)?;
+            assert_not_paused(pause.stake || pause.withdraw)?;
+            auto_compound(deps, env, staker)
+        }
+        ExecuteMsg::ClaimUnbonded {} => {
+            assert_not_paused(pause_state(deps.storage)?.unstake)?;
+            claim_unbonded(deps, env, info)
+        }
         ExecuteMsg::SetDistribution {
             nft_addr,
             start_date,
             end_date,
             amount,
-        } => set_distribution_schedule(deps, env, info, nft_addr, start_date, end_date, amount),
-        ExecuteMsg::UpdateConfig { config } => update_config(deps, env, info, config),
+            asset,
+        } => set_distribution_schedule(
+            deps, env, info, nft_addr, asset, start_date, end_date, amount,
+        ),
+        ExecuteMsg::UpdateConfig {
+            qtum_addr,
+            xqtum_addr,
+            nft_price,
+            native_nft_price,
+            disable_native_mint,
+            usdt_denom,
+            voting_period,
+            quorum,
+            threshold,
+            lock_tiers,
+            unbonding_period,
+        } => update_config(
+            deps,
+            env,
+            info,
+            qtum_addr,
+            xqtum_addr,
+            nft_price,
+            native_nft_price,
+            disable_native_mint,
+            usdt_denom,
+            voting_period,
+            quorum,
+            threshold,
+            lock_tiers,
+            unbonding_period,
+        ),
+        ExecuteMsg::AddGuild {
+            nft_addr,
+            reward_asset,
+        } => add_guild(deps, env, info, nft_addr, reward_asset),
+        ExecuteMsg::RemoveGuild { nft_addr } => remove_guild(deps, env, info, nft_addr),
+        ExecuteMsg::CancelSchedule { schedule_id } => cancel_schedule(deps, env, info, schedule_id),
+        ExecuteMsg::UpdateSchedule {
+            schedule_id,
+            end_date,
+        } => update_schedule(deps, env, info, schedule_id, end_date),
+        ExecuteMsg::PruneSchedules { nft_addr } => prune_schedules(deps, env, info, nft_addr),
+        ExecuteMsg::SetCollection {
+            nft_addr,
+            max_supply,
+            id_mode,
+        } => set_collection(deps, info, nft_addr, max_supply, id_mode),
+        ExecuteMsg::RevealSeed { nft_addr, seed } => reveal_seed(deps, info, nft_addr, seed),
+        ExecuteMsg::SetCollectionMetadata {
+            nft_addr,
+            base_token_uri,
+            placeholder_uri,
+            template,
+        } => set_collection_metadata(
+            deps,
+            info,
+            nft_addr,
+            base_token_uri,
+            placeholder_uri,
+            template,
+        ),
+        ExecuteMsg::Reveal { nft_addr } => reveal(deps, info, nft_addr),
+        ExecuteMsg::SetSalePhases { nft_addr, phases } => {
+            set_sale_phases(deps, info, nft_addr, phases)
+        }
+        ExecuteMsg::SetTokenMultipliers {
+            nft_addr,
+            multipliers,
+        } => set_token_multipliers(deps, env, info, nft_addr, multipliers),
+        ExecuteMsg::SetTraitMultipliers {
+            nft_addr,
+            multipliers,
+        } => set_trait_multipliers(deps, env, info, nft_addr, multipliers),
+        ExecuteMsg::Propose {
+            title,
+            description,
+            msgs,
+        } => propose(deps, env, info, title, description, msgs),
+        ExecuteMsg::Vote { proposal_id, vote } => {
+            cast_vote(deps, env, info.sender, proposal_id, vote)
+        }
+        ExecuteMsg::VoteFor {
+            proposal_id,
+            vote,
+            staker,
+        } => {
+            let staker = deps.api.addr_validate(&staker)?;
+            assert_staking_operator(deps.storage, &env, &staker, &info.sender)?;
+            cast_vote(deps, env, staker, proposal_id, vote)
+        }
+        ExecuteMsg::Execute { proposal_id } => execute_proposal(deps, env, info, proposal_id),
+        ExecuteMsg::Close { proposal_id } => close_proposal(deps, env, info, proposal_id),
+        ExecuteMsg::ProposeNewOwner { new_owner, expiry } => {
+            propose_new_owner(deps, env, info, new_owner, expiry)
+        }
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipProposal {} => cancel_ownership_proposal(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => renounce_ownership(deps, env, info),
+        ExecuteMsg::SetPause {
+            mint,
+            lock,
+            stake,
+            unstake,
+            withdraw,
+        } => set_pause(deps, env, info, mint, lock, stake, unstake, withdraw),
+        ExecuteMsg::GrantRole { role, address } => grant_role(deps, env, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => revoke_role(deps, env, info, role, address),
+        ExecuteMsg::EmergencyUnlock { nft_addr, token_id } => {
+            emergency_unlock(deps, env, info, nft_addr, token_id)
+        }
+        ExecuteMsg::ExpireLocks { nft_addr, token_id } => {
+            expire_locks(deps, env, info, nft_addr, token_id)
+        }
     }
 }
 
@@ -101,30 +307,76 @@ pub fn cw20_receive(
     cw20_msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
+    let pause = pause_state(deps.storage)?;
 
     match from_json(&cw20_msg.msg) {
-        Ok(Cw20HookMsg::Mint { id, nft_addr }) => {
+        Ok(Cw20HookMsg::Mint {
+            id,
+            nft_addr,
+            proof,
+        }) => {
+            assert_not_paused(pause.mint)?;
+
             // only qtum token contract can execute this message
             if config.qtum_addr != deps.api.addr_validate(info.sender.as_str())? {
                 return Err(ContractError::UnsupportedToken {});
             };
 
-            if config.nft_price > cw20_msg.amount {
-                return Err(ContractError::InsufficientToken {});
-            };
+            let cw20_sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            purchase_nft(
+                deps,
+                env,
+                cw20_sender,
+                nft_addr,
+                id,
+                proof,
+                AssetInfo::Cw20 {
+                    contract_addr: config.qtum_addr,
+                },
+                cw20_msg.amount,
+            )
+        }
 
+        Ok(Cw20HookMsg::FundSchedule {
+            nft_addr,
+            start_date,
+            end_date,
+        }) => {
             let cw20_sender = deps.api.addr_validate(&cw20_msg.sender)?;
-            mint_nft(cw20_sender, id, nft_addr)
+            fund_schedule(
+                deps,
+                env,
+                cw20_sender,
+                info.sender,
+                nft_addr,
+                start_date,
+                end_date,
+                cw20_msg.amount,
+            )
         }
 
-        Ok(Cw20HookMsg::Stake {}) => {
+        Ok(Cw20HookMsg::Stake {
+            nft_addr,
+            token_id,
+            lock_duration,
+        }) => {
+            assert_not_paused(pause.stake)?;
+
             // only qtum token contract can execute this message
             if config.xqtum_addr != deps.api.addr_validate(info.sender.as_str())? {
                 return Err(ContractError::UnsupportedToken {});
             };
 
             let cw20_sender = deps.api.addr_validate(&cw20_msg.sender)?;
-            stake_xqtum(deps, env, cw20_sender, cw20_msg.amount)
+            stake_xqtum(
+                deps,
+                env,
+                cw20_sender,
+                nft_addr,
+                token_id,
+                cw20_msg.amount,
+                lock_duration,
+            )
         }
 
         Err(_) => Err(ContractError::Unauthorized {}),
@@ -137,61 +389,93 @@ pub fn cw721_receive(
     info: MessageInfo,
     cw721_msg: Cw721ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-
     match from_json(&cw721_msg.msg) {
-        Ok(Cw721HookMsg::Lock { id }) => {
-            // only qtum token contract can execute this message
+        Ok(Cw721HookMsg::Lock {}) => {
+            assert_not_paused(pause_state(deps.storage)?.lock)?;
+
+            // only registered guild collections can execute this message
             let nft_addr = deps.api.addr_validate(info.sender.as_str())?;
 
-            if config.ninja_nft_addr != nft_addr && config.scientist_nft_addr != nft_addr {
-                return Err(ContractError::UnsupportedToken {});
+            if !GUILDS.has(deps.storage, &nft_addr) {
+                return Err(ContractError::UnsupportedNft {});
             };
 
             let sender = deps.api.addr_validate(&cw721_msg.sender)?;
-            lock_nft(deps, env, sender, id, nft_addr.to_string())
+            lock_nft(deps, env, sender, nft_addr, cw721_msg.token_id)
         }
 
         Err(_) => Err(ContractError::Unauthorized {}),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn stake_xqtum(
     deps: DepsMut,
     env: Env,
     sender: Addr,
+    nft_addr: String,
+    token_id: String,
     amount: Uint128,
+    lock_duration: Option<u64>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
+    let block_time = env.block.time.seconds();
+
+    let lock = match lock_duration {
+        Some(duration) => {
+            let tier = config
+                .lock_tiers
+                .iter()
+                .find(|tier| tier.duration == duration)
+                .ok_or(ContractError::InvalidLockDuration {})?;
+            Some(StakeLock {
+                amount,
+                end: block_time + tier.duration,
+                reward_multiplier: tier.reward_multiplier,
+                voting_multiplier: tier.voting_multiplier,
+            })
+        }
+        None => None,
+    };
 
-    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), sender.as_str())?;
-
-    if staker_info.token_id == None {
-        return Err(ContractError::CustomError {
-            msg: "lock  nft before you staking xqtum".to_string(),
-        });
-    }
-
-    let nft_addr = staker_info.nft_addr.clone().unwrap();
-    let mut guild_state: GuildState = guild_state(deps.as_ref(), nft_addr.clone())?;
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut position = operated_position(deps.as_ref(), &env, &sender, &nft_addr, &token_id)?;
+    let owner = position.owner.clone();
+    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), owner.as_str())?;
+    let mut guild_state: GuildState = guild_state(deps.as_ref(), &nft_addr)?;
 
-    compute_reward(
-        &config,
-        &mut guild_state,
-        env.block.time.seconds(),
-        nft_addr.clone(),
-    );
-    compute_staker_reward(&guild_state, &mut staker_info)?;
+    compute_reward(deps.storage, &nft_addr, &mut guild_state, block_time)?;
+    compute_staker_reward(deps.storage, &guild_state, &mut staker_info, &mut position)?;
     // Increase bond_amount
-    increase_stake_amount(&mut guild_state, &mut staker_info, amount);
+    let lock_end = lock.as_ref().map(|lock| lock.end);
+    update_position(
+        deps.storage,
+        &mut guild_state,
+        &mut staker_info,
+        &mut position,
+        |position| {
+            position.stake_amount += amount;
+            position.locks.extend(lock);
+        },
+    )?;
 
-    store_user_staking(deps.storage, sender.as_str(), &staker_info)?;
-    store_guild_state(deps.storage, &guild_state, nft_addr.clone())?;
+    positions().save(deps.storage, (&nft_addr, &token_id), &position)?;
+    store_user_staking(deps.storage, owner.as_str(), &staker_info, env.block.height)?;
+    store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
 
     Ok(Response::new().add_attributes(vec![
         ("action", "bond"),
-        ("owner", sender.as_str()),
+        ("owner", owner.as_str()),
+        ("sender", sender.as_str()),
+        ("nft_address", nft_addr.as_str()),
+        ("token_id", token_id.as_str()),
         ("amount", amount.to_string().as_str()),
+        (
+            "lock_end",
+            lock_end
+                .map_or("none".to_string(), |end| end.to_string())
+                .as_str(),
+        ),
     ]))
 }
 
@@ -199,349 +483,2601 @@ pub fn unstake_xqtum(
     deps: DepsMut,
     env: Env,
     sender: Addr,
+    nft_addr: String,
+    token_id: String,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
 
-    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), sender.as_str())?;
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut position = operated_position(deps.as_ref(), &env, &sender, &nft_addr, &token_id)?;
+    let owner = position.owner.clone();
 
-    if staker_info.stake_amount < amount {
+    if position.stake_amount < amount {
         return Err(ContractError::InsufficientToken {});
     }
 
-    let nft_addr = staker_info.nft_addr.clone().unwrap();
-    let mut guild_state: GuildState = guild_state(deps.as_ref(), nft_addr.clone())?;
-    compute_reward(
-        &config,
-        &mut guild_state,
-        env.block.time.seconds(),
-        nft_addr.clone(),
-    );
+    let block_time = env.block.time.seconds();
+    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), owner.as_str())?;
+    let mut guild_state: GuildState = guild_state(deps.as_ref(), &nft_addr)?;
+    compute_reward(deps.storage, &nft_addr, &mut guild_state, block_time)?;
 
-    compute_staker_reward(&guild_state, &mut staker_info)?;
+    compute_staker_reward(deps.storage, &guild_state, &mut staker_info, &mut position)?;
+    if position.stake_amount - position.locked_amount() < amount {
+        return Err(ContractError::StakeLocked {});
+    }
     // decrease bond_amount
-    decrease_stake_amount(&mut guild_state, &mut staker_info, amount);
+    decrease_stake_amount(
+        deps.storage,
+        &mut guild_state,
+        &mut staker_info,
+        &mut position,
+        amount,
+    )?;
 
-    store_user_staking(deps.storage, sender.as_str(), &staker_info)?;
-    store_guild_state(deps.storage, &guild_state, nft_addr.clone())?;
+    positions().save(deps.storage, (&nft_addr, &token_id), &position)?;
+    store_user_staking(deps.storage, owner.as_str(), &staker_info, env.block.height)?;
+    store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+
+    // operators never receive the stake
+    let unbond_msgs = unbond_xqtum(deps.storage, &env, &config, &owner, amount)?;
 
     Ok(Response::new()
-        .add_messages(vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.xqtum_addr.to_string(),
-            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: sender.to_string(),
-                amount,
-            })?,
-            funds: vec![],
-        })])
+        .add_messages(unbond_msgs)
         .add_attributes(vec![
             ("action", "unbond"),
-            ("owner", sender.to_string().as_str()),
+            ("owner", owner.as_str()),
+            ("sender", sender.as_str()),
+            ("nft_address", nft_addr.as_str()),
+            ("token_id", token_id.as_str()),
             ("amount", amount.to_string().as_str()),
         ]))
 }
 
-pub fn mint_nft(sender: Addr, id: String, nft_addr: String) -> Result<Response, ContractError> {
-    let mint_msg: Cw721ExecuteMsg<Empty, Empty> = Cw721ExecuteMsg::Mint(MintMsg {
-        owner: sender.to_string(),
-        token_id: id.to_string(),
-        // Some(format!("{}/{}", config.base_token_uri, mintable_token_id))
-        token_uri: None,
-        extension: Empty {},
-    });
+// queues the unstaked xqtum for the unbonding period or returns it right away
+fn unbond_xqtum(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    recipient: &Addr,
+    amount: Uint128,
+) -> StdResult<Vec<CosmosMsg>> {
+    if config.unbonding_period == 0 {
+        return Ok(vec![xqtum_transfer(config, recipient, amount)?]);
+    }
 
-    let msg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: nft_addr.to_string(),
-        msg: to_json_binary(&mint_msg)?,
-        funds: vec![],
-    });
+    CLAIMS.create_claim(
+        storage,
+        recipient,
+        amount,
+        Expiration::AtTime(env.block.time.plus_seconds(config.unbonding_period)),
+    )?;
+    let total_unbonding = TOTAL_UNBONDING.may_load(storage)?.unwrap_or_default();
+    TOTAL_UNBONDING.save(storage, &(total_unbonding + amount))?;
 
-    let res = Response::new()
-        .add_message(msg)
-        .add_attribute("action", "mint")
-        .add_attribute("nft_address", nft_addr)
-        .add_attribute("token_id", id)
-        .add_attribute("to", sender);
+    Ok(vec![])
+}
 
-    Ok(res)
+fn xqtum_transfer(config: &Config, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.xqtum_addr.to_string(),
+        msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    }))
 }
 
-pub fn lock_nft(
+pub fn claim_unbonded(
     deps: DepsMut,
-    _env: Env,
-    sender: Addr,
-    id: String,
-    nft_addr: String,
+    env: Env,
+    info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), sender.as_str())?;
-    let mut guild_state: GuildState = guild_state(deps.as_ref(), nft_addr.clone())?;
+    let config = CONFIG.load(deps.storage)?;
 
-    if staker_info.token_id != None {
-        return Err(ContractError::CustomError {
-            msg: "You already locked nft".to_string(),
-        });
+    let amount = CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, None)?;
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
     }
-
-    staker_info.token_id = Some(id.clone());
-    staker_info.nft_addr = Some(nft_addr.clone());
-    guild_state.total_staker += 1;
-
-    store_user_staking(deps.storage, sender.as_str(), &staker_info)?;
-    store_guild_state(deps.storage, &guild_state, nft_addr.clone())?;
+    TOTAL_UNBONDING.update(deps.storage, |total| -> StdResult<_> { Ok(total - amount) })?;
 
     Ok(Response::new()
-        .add_attribute("action", "lock_nft")
-        .add_attribute("owner", sender)
-        .add_attribute("nft_address", nft_addr.clone())
-        .add_attribute("token_id", id))
+        .add_message(xqtum_transfer(&config, &info.sender, amount)?)
+        .add_attributes(vec![
+            ("action", "claim_unbonded"),
+            ("to", info.sender.as_str()),
+            ("amount", amount.to_string().as_str()),
+        ]))
 }
 
-pub fn unlock_nft(
+// mint paid in qtum or the native denom, the part paid above the price is refunded
+#[allow(clippy::too_many_arguments)]
+pub fn purchase_nft(
     deps: DepsMut,
     env: Env,
-    sender: Addr,
-    id: String,
+    minter: Addr,
+    nft_addr: String,
+    id: Option<String>,
+    proof: Option<Vec<String>>,
+    payment: AssetInfo,
+    paid: Uint128,
 ) -> Result<Response, ContractError> {
-    let config: Config = CONFIG.load(deps.storage)?;
-    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), sender.as_str())?;
+    let config = CONFIG.load(deps.storage)?;
 
-    if staker_info.token_id != Some(id.clone()) {
-        return Err(ContractError::Unauthorized {});
-    }
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let token_id = record_collection_mint(deps.storage, &env, &minter, &nft_addr, id)?;
+    let metadata = COLLECTION_METADATA.may_load(deps.storage, &nft_addr)?;
+    let phase = record_phase_mint(deps, &env, &minter, &nft_addr, proof)?;
+
+    let price = match &payment {
+        AssetInfo::Cw20 { .. } => phase.price.unwrap_or(config.nft_price),
+        AssetInfo::Native { .. } => match phase.native_price {
+            Some(native_price) => native_price,
+            None => {
+                config
+                    .native_nft_price
+                    .ok_or(ContractError::NativeMintDisabled {})?
+                    .amount
+            }
+        },
+    };
+    if price > paid {
+        return Err(ContractError::InsufficientToken {});
+    };
 
-    let nft_addr = staker_info.nft_addr.clone().unwrap();
-    let mut guild_state: GuildState = guild_state(deps.as_ref(), nft_addr.clone())?;
-    let mut unlock_msg: Vec<CosmosMsg> = vec![];
+    let mut res = mint_nft(minter.clone(), token_id, nft_addr, metadata)?
+        .add_attribute("phase", phase.id.to_string())
+        .add_attribute("price", price.to_string());
 
-    // Compute global reward & staker reward
-    compute_reward(
-        &config,
-        &mut guild_state,
-        env.block.time.seconds(),
-        nft_addr.clone(),
-    );
-    compute_staker_reward(&guild_state, &mut staker_info)?;
-
-    let reward_amount = staker_info.pending_rewards;
-
-    if staker_info.stake_amount > Uint128::zero() {
-        let unstake_msg = CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.xqtum_addr.to_string(),
-            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: sender.to_string(),
-                amount: staker_info.stake_amount,
-            })?,
-            funds: vec![],
-        });
-        unlock_msg.push(unstake_msg);
+    let excess = paid - price;
+    if !excess.is_zero() {
+        res = res
+            .add_message(payment.transfer_msg(&minter, excess)?)
+            .add_attribute("refund", excess.to_string());
     }
 
-    if staker_info.pending_rewards > Uint128::zero() {
-        let claim_reward_msg;
+    Ok(res)
+}
 
-        if nft_addr == config.ninja_nft_addr {
-            claim_reward_msg = CosmosMsg::Bank(BankMsg::Send {
-                to_address: sender.to_string(),
-                amount: vec![Coin::new(reward_amount.u128(), "inj")],
-            });
-        } else {
-            claim_reward_msg = CosmosMsg::Bank(BankMsg::Send {
-                to_address: sender.to_string(),
-                amount: vec![Coin::new(reward_amount.u128(), config.usdt_denom)],
-            });
-        }
-        unlock_msg.push(claim_reward_msg);
+// only registered collections that are not sold out can be minted into,
+// returns the token id to mint
+fn record_collection_mint(
+    storage: &mut dyn Storage,
+    env: &Env,
+    minter: &Addr,
+    nft_addr: &Addr,
+    id: Option<String>,
+) -> Result<String, ContractError> {
+    let mut collection = COLLECTIONS
+        .may_load(storage, nft_addr)?
+        .ok_or(ContractError::UnsupportedNft {})?;
+
+    if collection.minted >= collection.max_supply {
+        return Err(ContractError::SoldOut {});
     }
 
-    let msg: Cw721ExecuteMsg<Empty, Empty> = Cw721ExecuteMsg::TransferNft {
-        recipient: sender.to_string(),
-        token_id: id.clone(),
+    let token_id = match (&collection.id_mode, id) {
+        (TokenIdMode::Chosen, Some(id)) => id,
+        (TokenIdMode::Sequential, None) => (collection.minted + 1).to_string(),
+        (TokenIdMode::Shuffled { .. }, None) => {
+            let seed = collection
+                .seed
+                .as_deref()
+                .ok_or(ContractError::SeedNotRevealed {})?;
+            draw_shuffled_id(storage, env, minter, nft_addr, &collection, seed)?.to_string()
+        }
+        _ => return Err(ContractError::InvalidTokenId {}),
     };
+    collection.minted += 1;
 
-    let transfer_nft_msg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: nft_addr.clone(),
-        msg: to_json_binary(&msg)?,
-        funds: vec![],
-    });
-
-    unlock_msg.push(transfer_nft_msg);
-
-    guild_state.total_staker -= 1;
-    guild_state.total_stake_amount -= staker_info.stake_amount;
-
-    remove_user_staking(deps.storage, sender.as_str())?;
-    store_guild_state(deps.storage, &guild_state, nft_addr.clone())?;
+    COLLECTIONS.save(storage, nft_addr, &collection)?;
+    Ok(token_id)
+}
 
-    Ok(Response::new()
-        .add_messages(unlock_msg)
-        .add_attribute("action", "unlock_nft")
-        .add_attribute("to", sender)
-        .add_attribute("nft_address", nft_addr.clone())
-        .add_attribute("token_id", id))
+// lazy fisher-yates over the pool 1..=max_supply, only the swapped entries are stored.
+// the first `minted` indices hold the ids drawn so far
+fn draw_shuffled_id(
+    storage: &mut dyn Storage,
+    env: &Env,
+    minter: &Addr,
+    nft_addr: &Addr,
+    collection: &Collection,
+    seed: &str,
+) -> StdResult<u64> {
+    let hash = Sha256::new()
+        .chain_update(seed.as_bytes())
+        .chain_update(nft_addr.as_bytes())
+        .chain_update(env.block.height.to_be_bytes())
+        .chain_update(env.block.time.nanos().to_be_bytes())
+        .chain_update(minter.as_bytes())
+        .chain_update(collection.minted.to_be_bytes())
+        .finalize();
+    let mut random = [0u8; 8];
+    random.copy_from_slice(&hash[..8]);
+
+    let index = collection.minted;
+    let picked = index + u64::from_be_bytes(random) % (collection.max_supply - index);
+
+    let id = SHUFFLED_IDS
+        .may_load(storage, (nft_addr, picked))?
+        .unwrap_or(picked + 1);
+    let current = SHUFFLED_IDS
+        .may_load(storage, (nft_addr, index))?
+        .unwrap_or(index + 1);
+    SHUFFLED_IDS.save(storage, (nft_addr, picked), &current)?;
+    SHUFFLED_IDS.remove(storage, (nft_addr, index));
+
+    Ok(id)
 }
 
-// withdraw rewards to executor
-pub fn withdraw_reward(
+pub fn set_collection(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
+    nft_addr: String,
+    max_supply: u64,
+    id_mode: TokenIdMode,
 ) -> Result<Response, ContractError> {
-    let config: Config = CONFIG.load(deps.storage)?;
-    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), info.sender.as_str())?;
-
-    let nft_addr = staker_info.nft_addr.clone().unwrap();
-    let mut guild_state: GuildState = guild_state(deps.as_ref(), nft_addr.clone())?;
-
-    let withdraw_msg: CosmosMsg<Empty>;
+    assert_role(deps.as_ref(), &info.sender, Role::MinterManager)?;
 
-    // Compute global reward & staker reward
-    compute_reward(
-        &config,
-        &mut guild_state,
-        env.block.time.seconds(),
-        nft_addr.clone(),
-    );
-    compute_staker_reward(&guild_state, &mut staker_info)?;
-
-    let amount = staker_info.pending_rewards;
-    staker_info.pending_rewards = Uint128::zero();
-    staker_info.reward_claimed += amount;
-
-    if nft_addr.clone() == config.ninja_nft_addr {
-        withdraw_msg = CosmosMsg::Bank(BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: vec![Coin::new(amount.u128(), "inj")],
-        });
-    } else {
-        withdraw_msg = CosmosMsg::Bank(BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: vec![Coin::new(amount.u128(), config.usdt_denom)],
-        });
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    if !GUILDS.has(deps.storage, &nft_addr) {
+        return Err(ContractError::UnsupportedNft {});
     }
 
-    store_user_staking(deps.storage, info.sender.as_str(), &staker_info)?;
-    store_guild_state(deps.storage, &guild_state, nft_addr.clone())?;
+    if let TokenIdMode::Shuffled { seed_hash } = &id_mode {
+        let mut hash = [0u8; 32];
+        hex::decode_to_slice(seed_hash, &mut hash)
+            .map_err(|_| ContractError::InvalidSeedHash {})?;
+    }
 
-    // Store updated state
+    let existing = COLLECTIONS.may_load(deps.storage, &nft_addr)?;
+    let minted = match &existing {
+        Some(collection) if collection.minted > 0 => {
+            // the shuffled pool depends on the supply
+            let shuffled = matches!(collection.id_mode, TokenIdMode::Shuffled { .. });
+            if collection.id_mode != id_mode || (shuffled && collection.max_supply != max_supply) {
+                return Err(ContractError::CollectionLocked {});
+            }
+            collection.minted
+        }
+        _ => 0,
+    };
+    if max_supply < minted {
+        return Err(ContractError::InvalidMaxSupply {});
+    }
+    // a new commitment drops the revealed seed
+    let seed = existing
+        .filter(|collection| collection.id_mode == id_mode)
+        .and_then(|collection| collection.seed);
+    COLLECTIONS.save(
+        deps.storage,
+        &nft_addr,
+        &Collection {
+            max_supply,
+            minted,
+            id_mode,
+            seed,
+        },
+    )?;
 
-    Ok(Response::new()
-        .add_message(withdraw_msg)
-        .add_attributes(vec![
-            ("action", "withdraw"),
-            ("owner", info.sender.as_str()),
-            ("amount", amount.to_string().as_str()),
-        ]))
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_collection"),
+        ("nft_address", nft_addr.as_str()),
+        ("max_supply", max_supply.to_string().as_str()),
+    ]))
 }
 
-pub fn update_config(
+pub fn reveal_seed(
     deps: DepsMut,
-    _env: Env,
     info: MessageInfo,
-    new_config: Config,
+    nft_addr: String,
+    seed: String,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-
-    if config.owner != info.sender {
-        return Err(ContractError::Unauthorized {});
+    assert_role(deps.as_ref(), &info.sender, Role::MinterManager)?;
+
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut collection = COLLECTIONS
+        .may_load(deps.storage, &nft_addr)?
+        .ok_or(ContractError::UnsupportedNft {})?;
+    let seed_hash = match &collection.id_mode {
+        TokenIdMode::Shuffled { seed_hash } => seed_hash,
+        _ => return Err(ContractError::InvalidTokenId {}),
+    };
+    if collection.seed.is_some() {
+        return Err(ContractError::AlreadyRevealed {});
+    }
+    if hex::encode(Sha256::digest(seed.as_bytes())) != seed_hash.to_lowercase() {
+        return Err(ContractError::InvalidSeed {});
     }
 
-    CONFIG.save(deps.storage, &new_config)?;
+    collection.seed = Some(seed.clone());
+    COLLECTIONS.save(deps.storage, &nft_addr, &collection)?;
 
-    Ok(Response::new().add_attributes(vec![("action", "update_config")]))
+    Ok(Response::new().add_attributes(vec![
+        ("action", "reveal_seed"),
+        ("nft_address", nft_addr.as_str()),
+        ("seed", seed.as_str()),
+    ]))
 }
 
-pub fn set_distribution_schedule(
+pub fn set_collection_metadata(
     deps: DepsMut,
-    _env: Env,
     info: MessageInfo,
     nft_addr: String,
-    start_date: u64,
-    end_date: u64,
-    amount: Uint128,
+    base_token_uri: Option<String>,
+    placeholder_uri: Option<String>,
+    template: Option<MetadataTemplate>,
 ) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
-    let mut guild_state: GuildState = guild_state(deps.as_ref(), nft_addr.clone())?;
-
-    if info.sender != config.owner {
-        return Err(ContractError::Unauthorized {});
+    assert_role(deps.as_ref(), &info.sender, Role::MinterManager)?;
+
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let collection = COLLECTIONS
+        .may_load(deps.storage, &nft_addr)?
+        .ok_or(ContractError::UnsupportedNft {})?;
+
+    let metadata = COLLECTION_METADATA.may_load(deps.storage, &nft_addr)?;
+    // minted tokens keep the uri they were minted with
+    let minted_base_token_uri = metadata
+        .as_ref()
+        .and_then(|metadata| metadata.base_token_uri.clone());
+    if collection.minted > 0 && minted_base_token_uri != base_token_uri {
+        return Err(ContractError::CollectionLocked {});
     }
 
-    if nft_addr == config.ninja_nft_addr {
-        config
-            .ninja_distribution_schedule
-            .push((start_date, end_date, amount));
-    } else {
-        config
-            .scientist_distribution_schedule
-            .push((start_date, end_date, amount));
-    };
-
-    guild_state.total_rewards_distributed += amount;
-
-    store_guild_state(deps.storage, &guild_state, nft_addr)?;
-
-    CONFIG.save(deps.storage, &config)?;
+    let revealed = metadata.is_some_and(|metadata| metadata.revealed);
+    COLLECTION_METADATA.save(
+        deps.storage,
+        &nft_addr,
+        &CollectionMetadata {
+            base_token_uri,
+            placeholder_uri,
+            revealed,
+            template,
+        },
+    )?;
 
-    Ok(Response::new().add_attributes(vec![("action", "update_config")]))
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_collection_metadata"),
+        ("nft_address", nft_addr.as_str()),
+    ]))
 }
 
-fn compute_reward(config: &Config, state: &mut GuildState, block_time: u64, nft_addr: String) {
-    if state.total_stake_amount.is_zero() {
-        state.last_distributed = block_time;
-        return;
-    };
+pub fn reveal(
+    deps: DepsMut,
+    info: MessageInfo,
+    nft_addr: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MinterManager)?;
+
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut metadata = COLLECTION_METADATA
+        .may_load(deps.storage, &nft_addr)?
+        .ok_or(ContractError::MissingBaseTokenUri {})?;
+    if metadata.revealed {
+        return Err(ContractError::AlreadyRevealed {});
+    }
 
-    let mut distributed_amount = Uint128::zero();
+    let base_token_uri = metadata
+        .base_token_uri
+        .clone()
+        .ok_or(ContractError::MissingBaseTokenUri {})?;
+    metadata.revealed = true;
+    COLLECTION_METADATA.save(deps.storage, &nft_addr, &metadata)?;
 
-    let distribution_schedule = if nft_addr == config.ninja_nft_addr {
-        &config.ninja_distribution_schedule
-    } else {
-        &config.scientist_distribution_schedule
-    };
+    Ok(Response::new().add_attributes(vec![
+        ("action", "reveal"),
+        ("nft_address", nft_addr.as_str()),
+        ("base_token_uri", base_token_uri.as_str()),
+    ]))
+}
 
-    for s in distribution_schedule.iter() {
-        if s.0 > block_time || s.1 < state.last_distributed {
-            continue;
+// checks the minter against the running sale phase and counts the mint
+fn record_phase_mint(
+    deps: DepsMut,
+    env: &Env,
+    minter: &Addr,
+    nft_addr: &Addr,
+    proof: Option<Vec<String>>,
+) -> Result<SalePhase, ContractError> {
+    let block_time = env.block.time.seconds();
+    let phase = SALE_PHASES
+        .may_load(deps.storage, nft_addr)?
+        .unwrap_or_default()
+        .into_iter()
+        .find(|phase| phase.is_active(block_time))
+        .ok_or(ContractError::SaleClosed {})?;
+
+    match &phase.kind {
+        SaleKind::Closed => return Err(ContractError::SaleClosed {}),
+        SaleKind::Allowlist { merkle_root } => {
+            let proof = proof.ok_or(ContractError::InvalidMerkleProof {})?;
+            verify_merkle_proof(merkle_root, &proof, minter.as_bytes())?;
         }
+        SaleKind::Public => {}
+    }
 
-        let passed_time =
-            std::cmp::min(s.1, block_time) - std::cmp::max(s.0, state.last_distributed);
-
-        let time = s.1 - s.0;
-        let distribution_amount_per_second = Decimal::from_ratio(s.2, time);
-        distributed_amount += distribution_amount_per_second * Uint128::from(passed_time as u128);
+    let minted = PHASE_MINTS
+        .may_load(deps.storage, (nft_addr, phase.id, minter))?
+        .unwrap_or_default()
+        + 1;
+    if phase.per_address_cap.is_some_and(|cap| minted > cap) {
+        return Err(ContractError::MintCapReached {});
     }
+    PHASE_MINTS.save(deps.storage, (nft_addr, phase.id, minter), &minted)?;
 
-    state.last_distributed = block_time;
-    state.global_reward_index = state.global_reward_index
-        + Decimal::from_ratio(distributed_amount, state.total_stake_amount);
+    Ok(phase)
 }
 
-fn compute_staker_reward(state: &GuildState, staker_info: &mut StakerInfo) -> StdResult<()> {
-    let pending_rewards = (staker_info.stake_amount * state.global_reward_index)
-        .checked_sub(staker_info.stake_amount * staker_info.reward_index)?;
+// pairs are hashed in sorted order, so the proof does not need the leaf positions
+fn verify_merkle_proof(root: &str, proof: &[String], leaf: &[u8]) -> Result<(), ContractError> {
+    let mut hash: [u8; 32] = Sha256::digest(leaf).into();
+
+    for sibling in proof {
+        let mut sibling_hash = [0u8; 32];
+        hex::decode_to_slice(sibling, &mut sibling_hash)
+            .map_err(|_| ContractError::InvalidMerkleProof {})?;
+
+        let (first, second) = if hash <= sibling_hash {
+            (hash, sibling_hash)
+        } else {
+            (sibling_hash, hash)
+        };
+        hash = Sha256::new()
+            .chain_update(first)
+            .chain_update(second)
+            .finalize()
+            .into();
+    }
 
-    staker_info.reward_index = state.global_reward_index;
-    staker_info.pending_rewards += pending_rewards;
+    if hex::encode(hash) != root.to_lowercase() {
+        return Err(ContractError::InvalidMerkleProof {});
+    }
     Ok(())
 }
 
-fn increase_stake_amount(state: &mut GuildState, staker_info: &mut StakerInfo, amount: Uint128) {
-    state.total_stake_amount += amount;
-    staker_info.stake_amount += amount;
-}
+pub fn set_sale_phases(
+    deps: DepsMut,
+    info: MessageInfo,
+    nft_addr: String,
+    phases: Vec<SalePhase>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MinterManager)?;
+
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    validate_sale_phases(&phases)?;
+    SALE_PHASES.save(deps.storage, &nft_addr, &phases)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_sale_phases"),
+        ("nft_address", nft_addr.as_str()),
+        ("phases", phases.len().to_string().as_str()),
+    ]))
+}
+
+pub fn mint_nft(
+    sender: Addr,
+    id: String,
+    nft_addr: Addr,
+    metadata: Option<CollectionMetadata>,
+) -> Result<Response, ContractError> {
+    let token_uri = metadata
+        .as_ref()
+        .and_then(|metadata| metadata.token_uri(&id));
+    let extension = match metadata.and_then(|metadata| metadata.template) {
+        Some(template) => MintExtension {
+            name: template
+                .name_prefix
+                .map(|name_prefix| format!("{} #{}", name_prefix, id)),
+            description: template.description,
+            attributes: Some(template.attributes),
+        },
+        None => MintExtension::default(),
+    };
+
+    let mint_msg: Cw721ExecuteMsg<MintExtension, Empty> = Cw721ExecuteMsg::Mint(MintMsg {
+        owner: sender.to_string(),
+        token_id: id.to_string(),
+        token_uri,
+        extension,
+    });
+
+    let msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: nft_addr.to_string(),
+        msg: to_json_binary(&mint_msg)?,
+        funds: vec![],
+    });
+
+    let res = Response::new()
+        .add_message(msg)
+        .add_attribute("action", "mint")
+        .add_attribute("nft_address", nft_addr)
+        .add_attribute("token_id", id)
+        .add_attribute("to", sender);
+
+    Ok(res)
+}
+
+pub fn lock_nft(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    nft_addr: Addr,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), sender.as_str())?;
+    let mut guild_state: GuildState = guild_state(deps.as_ref(), &nft_addr)?;
+    let multiplier = nft_multiplier(deps.as_ref(), &nft_addr, &token_id)?;
+
+    // the reward indices are set when xqtum is staked on the position
+    positions().save(
+        deps.storage,
+        (&nft_addr, &token_id),
+        &Position {
+            owner: sender.clone(),
+            nft_addr: nft_addr.clone(),
+            token_id: token_id.clone(),
+            stake_amount: Uint128::zero(),
+            multiplier,
+            locks: vec![],
+            rewards: vec![],
+        },
+    )?;
+    staker_info.positions += 1;
+    guild_state.total_staker += 1;
+
+    store_user_staking(
+        deps.storage,
+        sender.as_str(),
+        &staker_info,
+        env.block.height,
+    )?;
+    store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "lock_nft")
+        .add_attribute("owner", sender)
+        .add_attribute("nft_address", nft_addr)
+        .add_attribute("token_id", token_id)
+        .add_attribute("multiplier", multiplier.to_string()))
+}
+
+pub fn unlock_nft(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    nft_addr: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut position = owned_position(deps.as_ref(), &sender, &nft_addr, &token_id)?;
+    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), sender.as_str())?;
+    let mut guild_state: GuildState = guild_state(deps.as_ref(), &nft_addr)?;
+    let mut unlock_msg: Vec<CosmosMsg> = vec![];
+
+    // Compute global reward & staker reward
+    let block_time = env.block.time.seconds();
+    compute_reward(deps.storage, &nft_addr, &mut guild_state, block_time)?;
+    compute_staker_reward(deps.storage, &guild_state, &mut staker_info, &mut position)?;
+    if !position.locks.is_empty() {
+        return Err(ContractError::StakeLocked {});
+    }
+
+    if position.stake_amount > Uint128::zero() {
+        unlock_msg.extend(unbond_xqtum(
+            deps.storage,
+            &env,
+            &config,
+            &sender,
+            position.stake_amount,
+        )?);
+    }
+
+    let claimed = claim_staker_rewards(&mut guild_state, &mut position);
+    let recipient = reward_recipient(deps.storage, &sender)?;
+    let (claim_reward_msgs, reward_attrs) = reward_transfers(claimed, &recipient)?;
+    unlock_msg.extend(claim_reward_msgs);
+
+    let msg: Cw721ExecuteMsg<Empty, Empty> = Cw721ExecuteMsg::TransferNft {
+        recipient: sender.to_string(),
+        token_id: token_id.clone(),
+    };
+
+    let transfer_nft_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: nft_addr.to_string(),
+        msg: to_json_binary(&msg)?,
+        funds: vec![],
+    });
+
+    unlock_msg.push(transfer_nft_msg);
+
+    let stake_amount = position.stake_amount;
+    decrease_stake_amount(
+        deps.storage,
+        &mut guild_state,
+        &mut staker_info,
+        &mut position,
+        stake_amount,
+    )?;
+    guild_state.total_staker -= 1;
+
+    positions().remove(deps.storage, (&nft_addr, &token_id))?;
+    close_position(deps.storage, &sender, staker_info, env.block.height)?;
+    store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+
+    Ok(Response::new()
+        .add_messages(unlock_msg)
+        .add_attribute("action", "unlock_nft")
+        .add_attribute("to", sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("nft_address", nft_addr)
+        .add_attribute("token_id", token_id)
+        .add_attributes(reward_attrs))
+}
+
+// exit path that skips the reward computation, only available while unstake and withdraw are
+// both paused. pending rewards are forfeited and the whole stake, locked or not, is returned right
+// away, the unbonding queue can't be claimed while unstake is paused
+pub fn emergency_unlock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    nft_addr: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    if !pause_state(deps.storage)?.exits_paused() {
+        return Err(ContractError::NotPaused {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut position = owned_position(deps.as_ref(), &info.sender, &nft_addr, &token_id)?;
+    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), info.sender.as_str())?;
+    let mut guild_state: GuildState = guild_state(deps.as_ref(), &nft_addr)?;
+    let mut msgs: Vec<CosmosMsg> = vec![];
+
+    // settled up to the last distribution only, the forfeited rewards are no longer owed
+    compute_staker_reward(deps.storage, &guild_state, &mut staker_info, &mut position)?;
+    for reward in position.rewards.iter() {
+        if let Some(reward_state) = guild_state
+            .rewards
+            .iter_mut()
+            .find(|r| r.asset == reward.asset)
+        {
+            reward_state.total_rewards_distributed = reward_state
+                .total_rewards_distributed
+                .saturating_sub(reward.pending_rewards);
+        }
+    }
+
+    let stake_amount = position.stake_amount;
+    if !stake_amount.is_zero() {
+        msgs.push(xqtum_transfer(&config, &info.sender, stake_amount)?);
+    }
+
+    let transfer_nft: Cw721ExecuteMsg<Empty, Empty> = Cw721ExecuteMsg::TransferNft {
+        recipient: info.sender.to_string(),
+        token_id: token_id.clone(),
+    };
+    msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: nft_addr.to_string(),
+        msg: to_json_binary(&transfer_nft)?,
+        funds: vec![],
+    }));
+
+    // running locks are released as well, nothing stays behind in a paused contract
+    update_position(
+        deps.storage,
+        &mut guild_state,
+        &mut staker_info,
+        &mut position,
+        |position| {
+            position.stake_amount = Uint128::zero();
+            position.locks.clear();
+        },
+    )?;
+    guild_state.total_staker -= 1;
+
+    positions().remove(deps.storage, (&nft_addr, &token_id))?;
+    close_position(deps.storage, &info.sender, staker_info, env.block.height)?;
+    store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+
+    Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        ("action", "emergency_unlock"),
+        ("to", info.sender.as_str()),
+        ("nft_address", nft_addr.as_str()),
+        ("token_id", token_id.as_str()),
+        ("amount", stake_amount.to_string().as_str()),
+    ]))
+}
+
+pub fn expire_locks(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    nft_addr: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let block_time = env.block.time.seconds();
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut position = positions()
+        .may_load(deps.storage, (&nft_addr, &token_id))?
+        .ok_or(ContractError::PositionNotFound {})?;
+    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), position.owner.as_str())?;
+    let mut guild_state: GuildState = guild_state(deps.as_ref(), &nft_addr)?;
+
+    compute_reward(deps.storage, &nft_addr, &mut guild_state, block_time)?;
+    let expired = position
+        .locks
+        .iter()
+        .filter(|lock| lock.end <= block_time)
+        .count();
+    compute_staker_reward(deps.storage, &guild_state, &mut staker_info, &mut position)?;
+
+    positions().save(deps.storage, (&nft_addr, &token_id), &position)?;
+    store_user_staking(
+        deps.storage,
+        position.owner.as_str(),
+        &staker_info,
+        env.block.height,
+    )?;
+    store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "expire_locks"),
+        ("nft_address", nft_addr.as_str()),
+        ("token_id", token_id.as_str()),
+        ("expired", expired.to_string().as_str()),
+    ]))
+}
+
+// withdraw the rewards of every position of the staker to recipient
+pub fn withdraw_reward(
+    deps: DepsMut,
+    env: Env,
+    staker: Addr,
+    recipient: Addr,
+) -> Result<Response, ContractError> {
+    let block_time = env.block.time.seconds();
+    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), staker.as_str())?;
+    let mut claimed: Vec<(AssetInfo, Uint128)> = vec![];
+
+    for mut position in staker_positions(deps.storage, &staker)? {
+        let nft_addr = position.nft_addr.clone();
+        let mut guild_state: GuildState = guild_state(deps.as_ref(), &nft_addr)?;
+
+        // Compute global reward & staker reward
+        compute_reward(deps.storage, &nft_addr, &mut guild_state, block_time)?;
+        compute_staker_reward(deps.storage, &guild_state, &mut staker_info, &mut position)?;
+        claimed.extend(claim_staker_rewards(&mut guild_state, &mut position));
+
+        positions().save(deps.storage, (&nft_addr, &position.token_id), &position)?;
+        store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+    }
+
+    if staker_info.positions > 0 {
+        store_user_staking(
+            deps.storage,
+            staker.as_str(),
+            &staker_info,
+            env.block.height,
+        )?;
+    }
+
+    // every accrued reward asset is paid out in the same transaction
+    let (withdraw_msgs, reward_attrs) = reward_transfers(claimed, &recipient)?;
+
+    Ok(Response::new()
+        .add_messages(withdraw_msgs)
+        .add_attributes(vec![
+            ("action", "withdraw"),
+            ("owner", staker.as_str()),
+            ("recipient", recipient.as_str()),
+        ])
+        .add_attributes(reward_attrs))
+}
+
+pub fn set_reward_recipient(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let recipient = match recipient {
+        Some(recipient) => {
+            let recipient = deps.api.addr_validate(&recipient)?;
+            REWARD_RECIPIENTS.save(deps.storage, &info.sender, &recipient)?;
+            recipient
+        }
+        None => {
+            REWARD_RECIPIENTS.remove(deps.storage, &info.sender);
+            info.sender.clone()
+        }
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_reward_recipient"),
+        ("owner", info.sender.as_str()),
+        ("recipient", recipient.as_str()),
+    ]))
+}
+
+pub fn approve_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?;
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+
+    STAKING_OPERATORS.save(deps.storage, (&info.sender, &operator), &expires)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "approve_operator"),
+        ("owner", info.sender.as_str()),
+        ("operator", operator.as_str()),
+        ("expires", expires.to_string().as_str()),
+    ]))
+}
+
+pub fn revoke_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?;
+    STAKING_OPERATORS.remove(deps.storage, (&info.sender, &operator));
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "revoke_operator"),
+        ("owner", info.sender.as_str()),
+        ("operator", operator.as_str()),
+    ]))
+}
+
+pub fn approve_claim_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?;
+    CLAIM_OPERATORS.save(deps.storage, (&info.sender, &operator), &Empty {})?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "approve_claim_operator"),
+        ("owner", info.sender.as_str()),
+        ("operator", operator.as_str()),
+    ]))
+}
+
+pub fn revoke_claim_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?;
+    CLAIM_OPERATORS.remove(deps.storage, (&info.sender, &operator));
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "revoke_claim_operator"),
+        ("owner", info.sender.as_str()),
+        ("operator", operator.as_str()),
+    ]))
+}
+
+pub fn compound(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let amount = compound_rewards(deps, &env, &info.sender)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "compound"),
+        ("owner", info.sender.as_str()),
+        ("amount", amount.to_string().as_str()),
+    ]))
+}
+
+pub fn set_auto_compound(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+) -> Result<Response, ContractError> {
+    if enabled {
+        AUTO_COMPOUND.save(deps.storage, &info.sender, &Empty {})?;
+    } else {
+        AUTO_COMPOUND.remove(deps.storage, &info.sender);
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_auto_compound"),
+        ("owner", info.sender.as_str()),
+        ("enabled", enabled.to_string().as_str()),
+    ]))
+}
+
+pub fn auto_compound(deps: DepsMut, env: Env, staker: String) -> Result<Response, ContractError> {
+    let staker = deps.api.addr_validate(&staker)?;
+    if !AUTO_COMPOUND.has(deps.storage, &staker) {
+        return Err(ContractError::AutoCompoundDisabled {});
+    }
+
+    let amount = compound_rewards(deps, &env, &staker)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "auto_compound"),
+        ("owner", staker.as_str()),
+        ("amount", amount.to_string().as_str()),
+    ]))
+}
+
+// the xqtum rewards are already held by the contract, they only move from the
+// reward pool to the stake of the position. there is no swap route to xqtum, rewards
+// in other assets are left pending for Withdraw
+fn compound_rewards(deps: DepsMut, env: &Env, staker: &Addr) -> Result<Uint128, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let xqtum = AssetInfo::Cw20 {
+        contract_addr: config.xqtum_addr,
+    };
+    let block_time = env.block.time.seconds();
+    let mut staker_info: StakerInfo = user_staking(deps.as_ref(), staker.as_str())?;
+    let mut total_amount = Uint128::zero();
+
+    for mut position in staker_positions(deps.storage, staker)? {
+        let nft_addr = position.nft_addr.clone();
+        let mut guild_state: GuildState = guild_state(deps.as_ref(), &nft_addr)?;
+
+        compute_reward(deps.storage, &nft_addr, &mut guild_state, block_time)?;
+        compute_staker_reward(deps.storage, &guild_state, &mut staker_info, &mut position)?;
+
+        let amount = claim_staker_reward(&mut guild_state, &mut position, &xqtum);
+        update_position(
+            deps.storage,
+            &mut guild_state,
+            &mut staker_info,
+            &mut position,
+            |position| position.stake_amount += amount,
+        )?;
+        total_amount += amount;
+
+        positions().save(deps.storage, (&nft_addr, &position.token_id), &position)?;
+        store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+    }
+
+    if total_amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    store_user_staking(
+        deps.storage,
+        staker.as_str(),
+        &staker_info,
+        env.block.height,
+    )?;
+
+    Ok(total_amount)
+}
+
+// only the given fields are updated, schedules are managed with the schedule messages
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    qtum_addr: Option<String>,
+    xqtum_addr: Option<String>,
+    nft_price: Option<Uint128>,
+    native_nft_price: Option<Coin>,
+    disable_native_mint: Option<bool>,
+    usdt_denom: Option<String>,
+    voting_period: Option<u64>,
+    quorum: Option<Decimal>,
+    threshold: Option<Decimal>,
+    lock_tiers: Option<Vec<LockTier>>,
+    unbonding_period: Option<u64>,
+) -> Result<Response, ContractError> {
+    // the nft prices belong to the minter manager, every other field to the admin.
+    // the admin holds every role, so an update needs the minter manager role at least
+    assert_role(deps.as_ref(), &info.sender, Role::MinterManager)?;
+    if qtum_addr.is_some()
+        || xqtum_addr.is_some()
+        || usdt_denom.is_some()
+        || voting_period.is_some()
+        || quorum.is_some()
+        || threshold.is_some()
+        || lock_tiers.is_some()
+        || unbonding_period.is_some()
+    {
+        assert_role(deps.as_ref(), &info.sender, Role::Admin)?;
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+
+    if let Some(qtum_addr) = qtum_addr {
+        config.qtum_addr = deps.api.addr_validate(&qtum_addr)?;
+    }
+    if let Some(xqtum_addr) = xqtum_addr {
+        let xqtum_addr = deps.api.addr_validate(&xqtum_addr)?;
+        // staked and unbonding xqtum is paid back in the token it was sent in
+        if xqtum_addr != config.xqtum_addr && xqtum_outstanding(deps.storage)? {
+            return Err(ContractError::StakeOutstanding {});
+        }
+        config.xqtum_addr = xqtum_addr;
+    }
+    if let Some(nft_price) = nft_price {
+        validate_nft_price(nft_price)?;
+        config.nft_price = nft_price;
+    }
+    if disable_native_mint == Some(true) {
+        if native_nft_price.is_some() {
+            return Err(ContractError::NativeMintConflict {});
+        }
+        config.native_nft_price = None;
+    }
+    if let Some(native_nft_price) = native_nft_price {
+        validate_native_nft_price(&native_nft_price)?;
+        config.native_nft_price = Some(native_nft_price);
+    }
+    if let Some(usdt_denom) = usdt_denom {
+        validate_denom(&usdt_denom)?;
+        config.usdt_denom = usdt_denom;
+    }
+    if let Some(voting_period) = voting_period {
+        validate_voting_period(voting_period)?;
+        config.voting_period = voting_period;
+    }
+    if let Some(quorum) = quorum {
+        validate_threshold(quorum)?;
+        config.quorum = quorum;
+    }
+    if let Some(threshold) = threshold {
+        validate_threshold(threshold)?;
+        config.threshold = threshold;
+    }
+    if let Some(lock_tiers) = lock_tiers {
+        validate_lock_tiers(&lock_tiers)?;
+        config.lock_tiers = lock_tiers;
+    }
+    if let Some(unbonding_period) = unbonding_period {
+        config.unbonding_period = unbonding_period;
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![("action", "update_config")]))
+}
+
+fn xqtum_outstanding(storage: &dyn Storage) -> StdResult<bool> {
+    if !TOTAL_UNBONDING
+        .may_load(storage)?
+        .unwrap_or_default()
+        .is_zero()
+    {
+        return Ok(true);
+    }
+    for item in GUILDS.range(storage, None, None, Order::Ascending) {
+        let (_, guild_state) = item?;
+        if !guild_state.total_stake_amount.is_zero() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn set_distribution_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    nft_addr: String,
+    asset: Option<AssetInfo>,
+    start_date: u64,
+    end_date: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::RewardManager)?;
+
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut guild_state: GuildState = GUILDS
+        .may_load(deps.storage, &nft_addr)?
+        .ok_or(ContractError::UnsupportedNft {})?;
+
+    // schedules without an asset go to the default reward asset of the guild
+    let asset = match asset {
+        Some(asset) => validate_asset(deps.as_ref(), asset)?,
+        None => guild_state.rewards[0].asset.clone(),
+    };
+
+    // the rewards have to be attached, cw20 rewards are funded with the FundSchedule hook
+    match &asset {
+        AssetInfo::Native { denom } => {
+            if must_pay(&info, denom)? != amount {
+                return Err(ContractError::ScheduleFundsMismatch {});
+            }
+        }
+        AssetInfo::Cw20 { .. } => return Err(ContractError::UnsupportedToken {}),
+    }
+
+    let schedule_id = push_distribution_schedule(
+        deps.storage,
+        &mut guild_state,
+        Schedule {
+            nft_addr: nft_addr.clone(),
+            funder: info.sender.clone(),
+            asset,
+            start_date,
+            end_date,
+            amount,
+        },
+        env.block.time.seconds(),
+    )?;
+    store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_distribution"),
+        ("schedule_id", schedule_id.to_string().as_str()),
+    ]))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn fund_schedule(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    token: Addr,
+    nft_addr: String,
+    start_date: u64,
+    end_date: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &sender, Role::RewardManager)?;
+
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut guild_state: GuildState = GUILDS
+        .may_load(deps.storage, &nft_addr)?
+        .ok_or(ContractError::UnsupportedNft {})?;
+
+    let asset = AssetInfo::Cw20 {
+        contract_addr: token,
+    };
+
+    let schedule_id = push_distribution_schedule(
+        deps.storage,
+        &mut guild_state,
+        Schedule {
+            nft_addr: nft_addr.clone(),
+            funder: sender.clone(),
+            asset: asset.clone(),
+            start_date,
+            end_date,
+            amount,
+        },
+        env.block.time.seconds(),
+    )?;
+    store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "fund_schedule"),
+        ("schedule_id", schedule_id.to_string().as_str()),
+        ("nft_address", nft_addr.as_str()),
+        ("asset", asset.to_string().as_str()),
+        ("amount", amount.to_string().as_str()),
+    ]))
+}
+
+// a new asset starts a new reward index for the guild
+fn push_distribution_schedule(
+    storage: &mut dyn Storage,
+    guild_state: &mut GuildState,
+    schedule: Schedule,
+    block_time: u64,
+) -> Result<u64, ContractError> {
+    validate_schedule(&schedule, block_time)?;
+
+    // settle the running schedules before adding a new one
+    compute_reward(storage, &schedule.nft_addr, guild_state, block_time)?;
+
+    let reward_state = match guild_state
+        .rewards
+        .iter()
+        .position(|r| r.asset == schedule.asset)
+    {
+        Some(idx) => &mut guild_state.rewards[idx],
+        None => {
+            guild_state
+                .rewards
+                .push(new_reward_state(schedule.asset.clone()));
+            guild_state.rewards.last_mut().unwrap()
+        }
+    };
+    reward_state.total_rewards_distributed += schedule.amount;
+
+    let schedule_id = SCHEDULE_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    SCHEDULE_COUNT.save(storage, &schedule_id)?;
+    schedules().save(storage, schedule_id, &schedule)?;
+
+    Ok(schedule_id)
+}
+
+// cancels the unreleased part of a schedule and refunds it to the funder
+pub fn cancel_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    schedule_id: u64,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::RewardManager)?;
+
+    let block_time = env.block.time.seconds();
+    let mut schedule = schedules().load(deps.storage, schedule_id)?;
+    if schedule.end_date <= block_time {
+        return Err(ContractError::ScheduleFinished {});
+    }
+
+    let mut guild_state: GuildState = guild_state(deps.as_ref(), &schedule.nft_addr)?;
+    compute_reward(
+        deps.storage,
+        &schedule.nft_addr,
+        &mut guild_state,
+        block_time,
+    )?;
+
+    let released = schedule.released_amount(block_time);
+    let refund_amount = schedule.amount - released;
+
+    if released.is_zero() {
+        schedules().remove(deps.storage, schedule_id)?;
+    } else {
+        // keep the released part so the schedule ends now
+        schedule.end_date = block_time;
+        schedule.amount = released;
+        schedules().save(deps.storage, schedule_id, &schedule)?;
+    }
+
+    if let Some(reward_state) = guild_state
+        .rewards
+        .iter_mut()
+        .find(|r| r.asset == schedule.asset)
+    {
+        reward_state.total_rewards_distributed -= refund_amount;
+    }
+    store_guild_state(
+        deps.storage,
+        &guild_state,
+        &schedule.nft_addr,
+        env.block.height,
+    )?;
+
+    Ok(Response::new()
+        .add_message(
+            schedule
+                .asset
+                .transfer_msg(&schedule.funder, refund_amount)?,
+        )
+        .add_attributes(vec![
+            ("action", "cancel_schedule"),
+            ("schedule_id", schedule_id.to_string().as_str()),
+            ("funder", schedule.funder.as_str()),
+            ("refund_amount", refund_amount.to_string().as_str()),
+        ]))
+}
+
+// moves the end date of a schedule, the unreleased amount is spread until the new end date
+pub fn update_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    schedule_id: u64,
+    end_date: u64,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::RewardManager)?;
+
+    let block_time = env.block.time.seconds();
+    let mut schedule = schedules().load(deps.storage, schedule_id)?;
+    if schedule.end_date <= block_time {
+        return Err(ContractError::ScheduleFinished {});
+    }
+
+    let mut guild_state: GuildState = guild_state(deps.as_ref(), &schedule.nft_addr)?;
+    compute_reward(
+        deps.storage,
+        &schedule.nft_addr,
+        &mut guild_state,
+        block_time,
+    )?;
+
+    if end_date <= std::cmp::max(schedule.start_date, block_time) {
+        return Err(ContractError::InvalidScheduleRange {});
+    }
+
+    if schedule.start_date < block_time {
+        // the released part is already accounted in the reward index,
+        // the schedule continues from now with what is left
+        schedule.amount -= schedule.released_amount(block_time);
+        schedule.start_date = block_time;
+    }
+    schedule.end_date = end_date;
+
+    schedules().save(deps.storage, schedule_id, &schedule)?;
+    store_guild_state(
+        deps.storage,
+        &guild_state,
+        &schedule.nft_addr,
+        env.block.height,
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_schedule"),
+        ("schedule_id", schedule_id.to_string().as_str()),
+        ("end_date", end_date.to_string().as_str()),
+    ]))
+}
+
+// removes the schedules of a guild that are fully released
+pub fn prune_schedules(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    nft_addr: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::RewardManager)?;
+
+    let block_time = env.block.time.seconds();
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut guild_state: GuildState = guild_state(deps.as_ref(), &nft_addr)?;
+    compute_reward(deps.storage, &nft_addr, &mut guild_state, block_time)?;
+
+    let expired: Vec<u64> = guild_schedules(deps.storage, &nft_addr)?
+        .into_iter()
+        .filter(|(_, schedule)| schedule.end_date <= block_time)
+        .map(|(id, _)| id)
+        .collect();
+
+    for schedule_id in expired.iter() {
+        schedules().remove(deps.storage, *schedule_id)?;
+    }
+    store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "prune_schedules"),
+        ("nft_address", nft_addr.as_str()),
+        ("pruned", expired.len().to_string().as_str()),
+    ]))
+}
+
+pub fn add_guild(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    nft_addr: String,
+    reward_asset: Option<AssetInfo>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    if GUILDS.has(deps.storage, &nft_addr) {
+        return Err(ContractError::GuildAlreadyExists {});
+    }
+
+    let reward_asset = match reward_asset {
+        Some(asset) => validate_asset(deps.as_ref(), asset)?,
+        None => AssetInfo::Native {
+            denom: config.usdt_denom,
+        },
+    };
+
+    store_guild_state(
+        deps.storage,
+        &GuildState {
+            total_staker: 0,
+            total_stake_amount: Uint128::zero(),
+            total_effective_stake: Uint128::zero(),
+            total_voting_power: Uint128::zero(),
+            last_distributed: env.block.time.seconds(),
+            rewards: vec![new_reward_state(reward_asset.clone())],
+        },
+        &nft_addr,
+        env.block.height,
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "add_guild"),
+        ("nft_address", nft_addr.as_str()),
+        ("reward_asset", reward_asset.to_string().as_str()),
+    ]))
+}
+
+// guilds can only be removed once every nft has been unlocked
+pub fn remove_guild(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    nft_addr: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let guild_state: GuildState = GUILDS
+        .may_load(deps.storage, &nft_addr)?
+        .ok_or(ContractError::UnsupportedNft {})?;
+
+    if guild_state.total_staker > 0 {
+        return Err(ContractError::GuildNotEmpty {});
+    }
+
+    // running schedules have to be cancelled and finished ones pruned first
+    if !guild_schedules(deps.storage, &nft_addr)?.is_empty() {
+        return Err(ContractError::GuildHasSchedules {});
+    }
+
+    GUILDS.remove(deps.storage, &nft_addr);
+    // minting into the collection stops with the guild
+    COLLECTIONS.remove(deps.storage, &nft_addr);
+    COLLECTION_METADATA.remove(deps.storage, &nft_addr);
+    SALE_PHASES.remove(deps.storage, &nft_addr);
+    // a re-added guild starts with a fresh shuffled pool and mint counts
+    let shuffled_ids = SHUFFLED_IDS
+        .prefix(&nft_addr)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for index in shuffled_ids {
+        SHUFFLED_IDS.remove(deps.storage, (&nft_addr, index));
+    }
+    let phase_mints = PHASE_MINTS
+        .sub_prefix(&nft_addr)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (phase_id, minter) in phase_mints {
+        PHASE_MINTS.remove(deps.storage, (&nft_addr, phase_id, &minter));
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "remove_guild"),
+        ("nft_address", nft_addr.as_str()),
+    ]))
+}
+
+// locked nfts of the table are re-weighted right away
+pub fn set_token_multipliers(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    nft_addr: String,
+    multipliers: Vec<TokenMultiplier>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::RewardManager)?;
+
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let mut guild_state: GuildState = GUILDS
+        .may_load(deps.storage, &nft_addr)?
+        .ok_or(ContractError::UnsupportedNft {})?;
+
+    // settle the rewards released with the old weights
+    compute_reward(
+        deps.storage,
+        &nft_addr,
+        &mut guild_state,
+        env.block.time.seconds(),
+    )?;
+
+    for TokenMultiplier {
+        token_id,
+        multiplier,
+    } in multipliers
+    {
+        match multiplier {
+            Some(multiplier) => {
+                validate_multiplier(multiplier)?;
+                TOKEN_MULTIPLIERS.save(deps.storage, (&nft_addr, &token_id), &multiplier)?;
+            }
+            None => TOKEN_MULTIPLIERS.remove(deps.storage, (&nft_addr, &token_id)),
+        }
+
+        if let Some(mut position) = positions().may_load(deps.storage, (&nft_addr, &token_id))? {
+            let multiplier = nft_multiplier(deps.as_ref(), &nft_addr, &token_id)?;
+            let mut staker_info = user_staking(deps.as_ref(), position.owner.as_str())?;
+            compute_staker_reward(deps.storage, &guild_state, &mut staker_info, &mut position)?;
+            update_position(
+                deps.storage,
+                &mut guild_state,
+                &mut staker_info,
+                &mut position,
+                |position| position.multiplier = multiplier,
+            )?;
+
+            positions().save(deps.storage, (&nft_addr, &token_id), &position)?;
+            store_user_staking(
+                deps.storage,
+                position.owner.as_str(),
+                &staker_info,
+                env.block.height,
+            )?;
+        }
+    }
+
+    store_guild_state(deps.storage, &guild_state, &nft_addr, env.block.height)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_token_multipliers"),
+        ("nft_address", nft_addr.as_str()),
+    ]))
+}
+
+// trait multipliers apply to nfts locked afterwards
+pub fn set_trait_multipliers(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    nft_addr: String,
+    multipliers: Vec<TraitMultiplier>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::RewardManager)?;
+
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    if !GUILDS.has(deps.storage, &nft_addr) {
+        return Err(ContractError::UnsupportedNft {});
+    }
+
+    for TraitMultiplier {
+        trait_type,
+        value,
+        multiplier,
+    } in multipliers
+    {
+        let key = (&nft_addr, trait_type.as_str(), value.as_str());
+        match multiplier {
+            Some(multiplier) => {
+                validate_multiplier(multiplier)?;
+                TRAIT_MULTIPLIERS.save(deps.storage, key, &multiplier)?;
+            }
+            None => TRAIT_MULTIPLIERS.remove(deps.storage, key),
+        }
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_trait_multipliers"),
+        ("nft_address", nft_addr.as_str()),
+    ]))
+}
+
+// the new owner has to accept the ownership before the optional expiry
+pub fn propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    expiry: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    let expiry = expiry.unwrap_or_default();
+    if expiry.is_expired(&env.block) {
+        return Err(ContractError::OwnershipProposalExpired {});
+    }
+
+    PENDING_OWNER.save(
+        deps.storage,
+        &PendingOwner {
+            owner: new_owner.clone(),
+            expiry,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "propose_new_owner"),
+        ("new_owner", new_owner.as_str()),
+        ("expiry", expiry.to_string().as_str()),
+    ]))
+}
+
+pub fn accept_ownership(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingOwner {})?;
+
+    if pending.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pending.expiry.is_expired(&env.block) {
+        return Err(ContractError::OwnershipProposalExpired {});
+    }
+
+    OWNER.set(deps.branch(), Some(pending.owner))?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "accept_ownership"),
+        ("owner", info.sender.as_str()),
+    ]))
+}
+
+pub fn cancel_ownership_proposal(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+
+    if !PENDING_OWNER.exists(deps.storage) {
+        return Err(ContractError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new().add_attributes(vec![("action", "cancel_ownership_proposal")]))
+}
+
+// leaves the contract without an owner and admins, every owner and admin message is
+// rejected afterwards. the other role grants are kept and can no longer be changed
+pub fn renounce_ownership(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+
+    OWNER.set(deps.branch(), None)?;
+    PENDING_OWNER.remove(deps.storage);
+
+    // admins hold every role, they would keep full control otherwise
+    let admins = ROLES
+        .prefix(Role::Admin.as_str())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for admin in admins.iter() {
+        ROLES.remove(deps.storage, (Role::Admin.as_str(), admin));
+    }
+
+    Ok(Response::new().add_attributes(vec![("action", "renounce_ownership")]))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn set_pause(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    mint: Option<bool>,
+    lock: Option<bool>,
+    stake: Option<bool>,
+    unstake: Option<bool>,
+    withdraw: Option<bool>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Pauser)?;
+
+    let mut pause = pause_state(deps.storage)?;
+    pause.mint = mint.unwrap_or(pause.mint);
+    pause.lock = lock.unwrap_or(pause.lock);
+    pause.stake = stake.unwrap_or(pause.stake);
+    pause.unstake = unstake.unwrap_or(pause.unstake);
+    pause.withdraw = withdraw.unwrap_or(pause.withdraw);
+    PAUSE.save(deps.storage, &pause)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_pause"),
+        ("mint", pause.mint.to_string().as_str()),
+        ("lock", pause.lock.to_string().as_str()),
+        ("stake", pause.stake.to_string().as_str()),
+        ("unstake", pause.unstake.to_string().as_str()),
+        ("withdraw", pause.withdraw.to_string().as_str()),
+    ]))
+}
+
+// admins can manage every role except their own, the admin role is managed by the owner
+pub fn grant_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_role_manager(deps.as_ref(), &info.sender, &role)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    ROLES.save(deps.storage, (role.as_str(), &address), &Empty {})?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "grant_role"),
+        ("role", role.as_str()),
+        ("address", address.as_str()),
+    ]))
+}
+
+pub fn revoke_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_role_manager(deps.as_ref(), &info.sender, &role)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    if !ROLES.has(deps.storage, (role.as_str(), &address)) {
+        return Err(ContractError::RoleNotGranted {});
+    }
+    ROLES.remove(deps.storage, (role.as_str(), &address));
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "revoke_role"),
+        ("role", role.as_str()),
+        ("address", address.as_str()),
+    ]))
+}
+
+pub fn propose(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    title: String,
+    description: String,
+    msgs: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let start_height = env.block.height;
+    let start_time = env.block.time.seconds();
+
+    // only stakers can create proposals, voting power is taken at the beginning of this block
+    if voting_power_at_height(deps.storage, info.sender.as_str(), start_height, start_time)?
+        .is_zero()
+    {
+        return Err(ContractError::NoVotingPower {});
+    }
+
+    let mut total_power = Uint128::zero();
+    for nft_addr in GUILDS.keys(deps.storage, None, None, Order::Ascending) {
+        total_power +=
+            total_voting_power_at_height(deps.storage, &nft_addr?, start_height, start_time)?;
+    }
+
+    let proposal = Proposal {
+        proposer: info.sender.clone(),
+        title,
+        description,
+        msgs,
+        status: ProposalStatus::Open,
+        start_height,
+        start_time,
+        expires: start_time + config.voting_period,
+        quorum: config.quorum,
+        threshold: config.threshold,
+        total_power,
+        yes_votes: Uint128::zero(),
+        no_votes: Uint128::zero(),
+        abstain_votes: Uint128::zero(),
+    };
+
+    let proposal_id = PROPOSAL_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PROPOSAL_COUNT.save(deps.storage, &proposal_id)?;
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "propose"),
+        ("proposer", info.sender.as_str()),
+        ("proposal_id", proposal_id.to_string().as_str()),
+    ]))
+}
+
+pub fn cast_vote(
+    deps: DepsMut,
+    env: Env,
+    voter: Addr,
+    proposal_id: u64,
+    vote: VoteOption,
+) -> Result<Response, ContractError> {
+    let mut proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+
+    if proposal.status != ProposalStatus::Open || env.block.time.seconds() >= proposal.expires {
+        return Err(ContractError::ProposalNotOpen {});
+    }
+
+    if BALLOTS.has(deps.storage, (proposal_id, &voter)) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+
+    let weight = voting_power_at_height(
+        deps.storage,
+        voter.as_str(),
+        proposal.start_height,
+        proposal.start_time,
+    )?;
+    if weight.is_zero() {
+        return Err(ContractError::NoVotingPower {});
+    }
+
+    match vote {
+        VoteOption::Yes => proposal.yes_votes += weight,
+        VoteOption::No => proposal.no_votes += weight,
+        VoteOption::Abstain => proposal.abstain_votes += weight,
+    }
+
+    BALLOTS.save(
+        deps.storage,
+        (proposal_id, &voter),
+        &Ballot {
+            vote: vote.clone(),
+            weight,
+        },
+    )?;
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "vote"),
+        ("voter", voter.as_str()),
+        ("proposal_id", proposal_id.to_string().as_str()),
+        ("weight", weight.to_string().as_str()),
+    ]))
+}
+
+// anyone can execute a passed proposal, its messages are dispatched by the contract
+pub fn execute_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+
+    if proposal.current_status(env.block.time.seconds()) != ProposalStatus::Passed {
+        return Err(ContractError::WrongExecuteStatus {});
+    }
+
+    proposal.status = ProposalStatus::Executed;
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+    Ok(Response::new()
+        .add_messages(proposal.msgs)
+        .add_attributes(vec![
+            ("action", "execute"),
+            ("sender", info.sender.as_str()),
+            ("proposal_id", proposal_id.to_string().as_str()),
+        ]))
+}
+
+pub fn close_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let mut proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+
+    if proposal.status != ProposalStatus::Open
+        || proposal.current_status(env.block.time.seconds()) != ProposalStatus::Rejected
+    {
+        return Err(ContractError::WrongCloseStatus {});
+    }
+
+    proposal.status = ProposalStatus::Rejected;
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "close"),
+        ("sender", info.sender.as_str()),
+        ("proposal_id", proposal_id.to_string().as_str()),
+    ]))
+}
+
+fn validate_asset(deps: Deps, asset: AssetInfo) -> Result<AssetInfo, ContractError> {
+    Ok(match asset {
+        AssetInfo::Native { denom } => {
+            validate_denom(&denom)?;
+            AssetInfo::Native { denom }
+        }
+        AssetInfo::Cw20 { contract_addr } => AssetInfo::Cw20 {
+            contract_addr: deps.api.addr_validate(contract_addr.as_str())?,
+        },
+    })
+}
+
+fn validate_schedule(schedule: &Schedule, block_time: u64) -> Result<(), ContractError> {
+    if schedule.start_date >= schedule.end_date {
+        return Err(ContractError::InvalidScheduleRange {});
+    }
+    if schedule.start_date < block_time {
+        return Err(ContractError::ScheduleStartInPast {});
+    }
+    if schedule.amount.is_zero() {
+        return Err(ContractError::ZeroScheduleAmount {});
+    }
+    Ok(())
+}
+
+fn assert_owner(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if !OWNER.is_admin(deps, sender)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+// same rules as the cosmos sdk denom regex
+fn validate_denom(denom: &str) -> Result<(), ContractError> {
+    let valid = (3..=128).contains(&denom.len())
+        && denom.starts_with(|c: char| c.is_ascii_alphabetic())
+        && denom
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | ':' | '.' | '_' | '-'));
+    if !valid {
+        return Err(ContractError::InvalidDenom {
+            denom: denom.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn validate_sale_phases(phases: &[SalePhase]) -> Result<(), ContractError> {
+    for (i, phase) in phases.iter().enumerate() {
+        if phase.start >= phase.end
+            || phases[..i]
+                .iter()
+                .any(|p| p.id == phase.id || (p.start < phase.end && phase.start < p.end))
+        {
+            return Err(ContractError::InvalidSalePhase {});
+        }
+        if let SaleKind::Allowlist { merkle_root } = &phase.kind {
+            let mut root = [0u8; 32];
+            hex::decode_to_slice(merkle_root, &mut root)
+                .map_err(|_| ContractError::InvalidSalePhase {})?;
+        }
+        if phase.price.is_some_and(|price| price.is_zero())
+            || phase.native_price.is_some_and(|price| price.is_zero())
+        {
+            return Err(ContractError::ZeroNftPrice {});
+        }
+    }
+    Ok(())
+}
+
+fn validate_nft_price(nft_price: Uint128) -> Result<(), ContractError> {
+    if nft_price.is_zero() {
+        return Err(ContractError::ZeroNftPrice {});
+    }
+    Ok(())
+}
+
+fn validate_native_nft_price(native_nft_price: &Coin) -> Result<(), ContractError> {
+    validate_denom(&native_nft_price.denom)?;
+    validate_nft_price(native_nft_price.amount)
+}
+
+fn validate_voting_period(voting_period: u64) -> Result<(), ContractError> {
+    if voting_period == 0 {
+        return Err(ContractError::ZeroVotingPeriod {});
+    }
+    Ok(())
+}
+
+// the owner and admins hold every role
+fn assert_role(deps: Deps, sender: &Addr, role: Role) -> Result<(), ContractError> {
+    if OWNER.is_admin(deps, sender)?
+        || ROLES.has(deps.storage, (Role::Admin.as_str(), sender))
+        || ROLES.has(deps.storage, (role.as_str(), sender))
+    {
+        return Ok(());
+    }
+    Err(ContractError::Unauthorized {})
+}
+
+fn assert_role_manager(deps: Deps, sender: &Addr, role: &Role) -> Result<(), ContractError> {
+    match role {
+        Role::Admin => assert_owner(deps, sender),
+        _ => assert_role(deps, sender, Role::Admin),
+    }
+}
+
+fn validate_lock_tiers(lock_tiers: &[LockTier]) -> Result<(), ContractError> {
+    for (i, tier) in lock_tiers.iter().enumerate() {
+        if tier.duration == 0
+            || tier.reward_multiplier < Decimal::one()
+            || tier.voting_multiplier < Decimal::one()
+            || lock_tiers[..i].iter().any(|t| t.duration == tier.duration)
+        {
+            return Err(ContractError::InvalidLockTier {});
+        }
+    }
+    Ok(())
+}
+
+fn validate_multiplier(multiplier: Decimal) -> Result<(), ContractError> {
+    if multiplier.is_zero() {
+        return Err(ContractError::InvalidMultiplier {});
+    }
+    Ok(())
+}
+
+fn assert_not_paused(paused: bool) -> Result<(), ContractError> {
+    if paused {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+fn validate_threshold(value: Decimal) -> Result<(), ContractError> {
+    if value.is_zero() || value > Decimal::one() {
+        return Err(ContractError::InvalidThreshold {});
+    }
+    Ok(())
+}
+
+fn new_reward_state(asset: AssetInfo) -> RewardState {
+    RewardState {
+        asset,
+        total_rewards_distributed: Uint128::zero(),
+        total_rewards_claimed: Uint128::zero(),
+        global_reward_index: Decimal::zero(),
+    }
+}
+
+fn compute_reward(
+    storage: &mut dyn Storage,
+    nft_addr: &Addr,
+    state: &mut GuildState,
+    block_time: u64,
+) -> StdResult<()> {
+    for (end, expiry) in distribute_reward(storage, nft_addr, state, block_time)? {
+        LOCK_EXPIRIES.save(storage, (nft_addr, end), &expiry)?;
+    }
+    Ok(())
+}
+
+// distributes the rewards released up to block_time. the lock ends on the way split the
+// distribution, their boost is taken off the totals at the end. returns the passed lock ends
+// with the reward indices at their end
+fn distribute_reward(
+    storage: &dyn Storage,
+    nft_addr: &Addr,
+    state: &mut GuildState,
+    block_time: u64,
+) -> StdResult<Vec<(u64, LockExpiry)>> {
+    let schedules = guild_schedules(storage, nft_addr)?;
+    let mut expiries = vec![];
+
+    if block_time > state.last_distributed {
+        expiries = LOCK_EXPIRIES
+            .prefix(nft_addr)
+            .range(
+                storage,
+                Some(Bound::exclusive(state.last_distributed)),
+                Some(Bound::inclusive(block_time)),
+                Order::Ascending,
+            )
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+
+    for (end, expiry) in expiries.iter_mut() {
+        release_rewards(&schedules, state, *end);
+        state.total_effective_stake -= expiry.effective_stake;
+        state.total_voting_power -= expiry.voting_power;
+        expiry.reward_indices = state
+            .rewards
+            .iter()
+            .map(|reward| reward.global_reward_index)
+            .collect();
+    }
+    release_rewards(&schedules, state, block_time);
+
+    Ok(expiries)
+}
+
+fn release_rewards(schedules: &[(u64, Schedule)], state: &mut GuildState, block_time: u64) {
+    if state.total_effective_stake.is_zero() {
+        state.last_distributed = block_time;
+        return;
+    };
+
+    for reward in state.rewards.iter_mut() {
+        let mut distributed_amount = Uint128::zero();
+
+        for (_, s) in schedules.iter().filter(|(_, s)| s.asset == reward.asset) {
+            distributed_amount += s
+                .released_amount(block_time)
+                .saturating_sub(s.released_amount(state.last_distributed));
+        }
+
+        reward.global_reward_index +=
+            Decimal::from_ratio(distributed_amount, state.total_effective_stake);
+    }
+
+    state.last_distributed = block_time;
+}
+
+// settles the rewards of the position up to the last distribution. locks that ended before
+// earn with their boost up to their end and are dropped
+fn compute_staker_reward(
+    storage: &dyn Storage,
+    state: &GuildState,
+    staker_info: &mut StakerInfo,
+    position: &mut Position,
+) -> StdResult<()> {
+    let mut ends: Vec<u64> = position
+        .locks
+        .iter()
+        .map(|lock| lock.end)
+        .filter(|end| *end <= state.last_distributed)
+        .collect();
+    ends.sort_unstable();
+    ends.dedup();
+
+    for end in ends {
+        // no entry means the lock end changed no weight
+        if let Some(expiry) = LOCK_EXPIRIES.may_load(storage, (&position.nft_addr, end))? {
+            // assets added after the end had no index yet
+            let indices = state.rewards.iter().enumerate().map(|(idx, reward)| {
+                let index = expiry.reward_indices.get(idx).copied();
+                (&reward.asset, index.unwrap_or_default())
+            });
+            accrue_rewards(position, indices)?;
+        }
+
+        for lock in position.locks.iter().filter(|lock| lock.end == end) {
+            let bonus = lock.voting_bonus();
+            staker_info.voting_power -= bonus.amount;
+            remove_lock_bonus(staker_info, &bonus);
+        }
+        position.locks.retain(|lock| lock.end != end);
+    }
+
+    let indices = state
+        .rewards
+        .iter()
+        .map(|reward| (&reward.asset, reward.global_reward_index));
+    accrue_rewards(position, indices)
+}
+
+fn accrue_rewards<'a>(
+    position: &mut Position,
+    indices: impl Iterator<Item = (&'a AssetInfo, Decimal)>,
+) -> StdResult<()> {
+    let effective_stake = position.effective_stake();
+    for (asset, global_reward_index) in indices {
+        let staker_reward = match position.rewards.iter().position(|r| r.asset == *asset) {
+            Some(idx) => &mut position.rewards[idx],
+            None => {
+                position.rewards.push(StakerReward {
+                    asset: asset.clone(),
+                    pending_rewards: Uint128::zero(),
+                    reward_index: Decimal::zero(),
+                    reward_claimed: Uint128::zero(),
+                });
+                position.rewards.last_mut().unwrap()
+            }
+        };
+
+        let pending_rewards = (effective_stake * global_reward_index)
+            .checked_sub(effective_stake * staker_reward.reward_index)?;
+
+        staker_reward.reward_index = global_reward_index;
+        staker_reward.pending_rewards += pending_rewards;
+    }
+    Ok(())
+}
+
+// moves the pending rewards of the position to claimed and returns the claimed amounts
+fn claim_staker_rewards(
+    state: &mut GuildState,
+    position: &mut Position,
+) -> Vec<(AssetInfo, Uint128)> {
+    let mut claimed = vec![];
+
+    for reward in position.rewards.iter_mut() {
+        if reward.pending_rewards.is_zero() {
+            continue;
+        }
+
+        let amount = reward.pending_rewards;
+        reward.pending_rewards = Uint128::zero();
+        reward.reward_claimed += amount;
+
+        if let Some(reward_state) = state.rewards.iter_mut().find(|r| r.asset == reward.asset) {
+            reward_state.total_rewards_claimed += amount;
+        }
+
+        claimed.push((reward.asset.clone(), amount));
+    }
+
+    claimed
+}
+
+fn claim_staker_reward(
+    state: &mut GuildState,
+    position: &mut Position,
+    asset: &AssetInfo,
+) -> Uint128 {
+    let reward = match position.rewards.iter_mut().find(|r| r.asset == *asset) {
+        Some(reward) => reward,
+        None => return Uint128::zero(),
+    };
+
+    let amount = reward.pending_rewards;
+    reward.pending_rewards = Uint128::zero();
+    reward.reward_claimed += amount;
+
+    if let Some(reward_state) = state.rewards.iter_mut().find(|r| r.asset == *asset) {
+        reward_state.total_rewards_claimed += amount;
+    }
+
+    amount
+}
+
+// the stored reward recipient, the staker itself if none is set
+fn reward_recipient(storage: &dyn Storage, staker: &Addr) -> StdResult<Addr> {
+    Ok(REWARD_RECIPIENTS
+        .may_load(storage, staker)?
+        .unwrap_or_else(|| staker.clone()))
+}
+
+// one transfer per asset for the claimed rewards of one or more positions
+fn reward_transfers(
+    claimed: Vec<(AssetInfo, Uint128)>,
+    recipient: &Addr,
+) -> StdResult<(Vec<CosmosMsg>, Vec<Attribute>)> {
+    let mut totals: Vec<(AssetInfo, Uint128)> = vec![];
+    for (asset, amount) in claimed {
+        match totals.iter_mut().find(|(a, _)| *a == asset) {
+            Some((_, total)) => *total += amount,
+            None => totals.push((asset, amount)),
+        }
+    }
+
+    let mut msgs = vec![];
+    let mut attrs = vec![];
+    for (asset, amount) in totals {
+        msgs.push(asset.transfer_msg(recipient, amount)?);
+        attrs.push(attr("reward", format!("{}{}", amount, asset)));
+    }
+
+    Ok((msgs, attrs))
+}
+
+// applies a change of the position to the guild and staker totals. the guild totals hold the
+// locks ending after the last distribution, the staker totals every lock of the position
+fn update_position(
+    storage: &mut dyn Storage,
+    state: &mut GuildState,
+    staker_info: &mut StakerInfo,
+    position: &mut Position,
+    update: impl FnOnce(&mut Position),
+) -> StdResult<()> {
+    let since = state.last_distributed;
+
+    update_lock_expiries(storage, since, position, false)?;
+    state.total_stake_amount -= position.stake_amount;
+    state.total_effective_stake -= position.effective_stake_at(since);
+    state.total_voting_power -= position.voting_power_at(since);
+    staker_info.stake_amount -= position.stake_amount;
+    staker_info.voting_power -= position.voting_power();
+    for lock in position.locks.iter() {
+        remove_lock_bonus(staker_info, &lock.voting_bonus());
+    }
 
-fn decrease_stake_amount(state: &mut GuildState, staker_info: &mut StakerInfo, amount: Uint128) {
-    state.total_stake_amount -= amount;
-    staker_info.stake_amount -= amount;
+    update(position);
+
+    update_lock_expiries(storage, since, position, true)?;
+    state.total_stake_amount += position.stake_amount;
+    state.total_effective_stake += position.effective_stake_at(since);
+    state.total_voting_power += position.voting_power_at(since);
+    staker_info.stake_amount += position.stake_amount;
+    staker_info.voting_power += position.voting_power();
+    staker_info
+        .lock_bonuses
+        .extend(position.locks.iter().map(StakeLock::voting_bonus));
+    Ok(())
+}
+
+fn decrease_stake_amount(
+    storage: &mut dyn Storage,
+    state: &mut GuildState,
+    staker_info: &mut StakerInfo,
+    position: &mut Position,
+    amount: Uint128,
+) -> StdResult<()> {
+    update_position(storage, state, staker_info, position, |position| {
+        position.stake_amount -= amount
+    })
+}
+
+// adds or takes back the weight the position loses at each of its lock ends after since
+fn update_lock_expiries(
+    storage: &mut dyn Storage,
+    since: u64,
+    position: &Position,
+    add: bool,
+) -> StdResult<()> {
+    let mut ends: Vec<u64> = position
+        .locks
+        .iter()
+        .map(|lock| lock.end)
+        .filter(|end| *end > since)
+        .collect();
+    ends.sort_unstable();
+    ends.dedup();
+
+    let mut effective_stake = position.effective_stake_at(since);
+    let mut voting_power = position.voting_power_at(since);
+    for end in ends {
+        let key = (&position.nft_addr, end);
+        let mut expiry = LOCK_EXPIRIES.may_load(storage, key)?.unwrap_or_default();
+        let (lost_stake, lost_power) = (
+            effective_stake - position.effective_stake_at(end),
+            voting_power - position.voting_power_at(end),
+        );
+        if add {
+            expiry.effective_stake += lost_stake;
+            expiry.voting_power += lost_power;
+        } else {
+            expiry.effective_stake -= lost_stake;
+            expiry.voting_power -= lost_power;
+        }
+
+        if expiry.effective_stake.is_zero() && expiry.voting_power.is_zero() {
+            LOCK_EXPIRIES.remove(storage, key);
+        } else {
+            LOCK_EXPIRIES.save(storage, key, &expiry)?;
+        }
+        effective_stake = position.effective_stake_at(end);
+        voting_power = position.voting_power_at(end);
+    }
+    Ok(())
+}
+
+fn remove_lock_bonus(staker_info: &mut StakerInfo, bonus: &LockBonus) {
+    if let Some(idx) = staker_info.lock_bonuses.iter().position(|b| b == bonus) {
+        staker_info.lock_bonuses.remove(idx);
+    }
+}
+
+// the token table entry, otherwise the highest multiplier of the nft traits, otherwise one
+fn nft_multiplier(deps: Deps, nft_addr: &Addr, token_id: &str) -> StdResult<Decimal> {
+    if let Some(multiplier) = TOKEN_MULTIPLIERS.may_load(deps.storage, (nft_addr, token_id))? {
+        return Ok(multiplier);
+    }
+
+    // collections without a metadata extension simply get no trait boost
+    let metadata = deps
+        .querier
+        .query_wasm_smart::<NftInfoResponse<Option<NftMetadata>>>(
+            nft_addr,
+            &Cw721QueryMsg::NftInfo {
+                token_id: token_id.to_string(),
+            },
+        )
+        .ok()
+        .and_then(|info| info.extension)
+        .unwrap_or_default();
+
+    let mut multiplier: Option<Decimal> = None;
+    for nft_trait in metadata.attributes.unwrap_or_default() {
+        let key = (
+            nft_addr,
+            nft_trait.trait_type.as_str(),
+            nft_trait.value.as_str(),
+        );
+        if let Some(trait_multiplier) = TRAIT_MULTIPLIERS.may_load(deps.storage, key)? {
+            multiplier = Some(multiplier.map_or(trait_multiplier, |m| m.max(trait_multiplier)));
+        }
+    }
+
+    Ok(multiplier.unwrap_or_else(Decimal::one))
+}
+
+// the position if sender is its owner or an approved staking operator of the owner
+fn operated_position(
+    deps: Deps,
+    env: &Env,
+    sender: &Addr,
+    nft_addr: &Addr,
+    token_id: &str,
+) -> Result<Position, ContractError> {
+    let position = positions()
+        .may_load(deps.storage, (nft_addr, token_id))?
+        .ok_or(ContractError::PositionNotFound {})?;
+
+    assert_staking_operator(deps.storage, env, &position.owner, sender)?;
+    Ok(position)
+}
+
+fn assert_staking_operator(
+    storage: &dyn Storage,
+    env: &Env,
+    staker: &Addr,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    if staker == sender {
+        return Ok(());
+    }
+    match STAKING_OPERATORS.may_load(storage, (staker, sender))? {
+        Some(expires) if !expires.is_expired(&env.block) => Ok(()),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+fn owned_position(
+    deps: Deps,
+    owner: &Addr,
+    nft_addr: &Addr,
+    token_id: &str,
+) -> Result<Position, ContractError> {
+    let position = positions()
+        .may_load(deps.storage, (nft_addr, token_id))?
+        .ok_or(ContractError::PositionNotFound {})?;
+
+    if position.owner != *owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(position)
+}
+
+// updates the staker totals after a position was removed
+fn close_position(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    mut staker_info: StakerInfo,
+    height: u64,
+) -> StdResult<()> {
+    staker_info.positions -= 1;
+    if staker_info.positions == 0 {
+        remove_user_staking(storage, owner.as_str(), height)?;
+    } else {
+        store_user_staking(storage, owner.as_str(), &staker_info, height)?;
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidContractName {
+            name: stored.contract,
+        });
+    }
+
+    let stored_version = parse_version(&stored.version)?;
+    let current_version = parse_version(CONTRACT_VERSION)?;
+    if stored_version > current_version {
+        return Err(ContractError::CannotDowngrade {
+            stored: stored.version,
+            current: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    if let Some(voting_period) = msg.voting_period {
+        validate_voting_period(voting_period)?;
+    }
+    if let Some(quorum) = msg.quorum {
+        validate_threshold(quorum)?;
+    }
+    if let Some(threshold) = msg.threshold {
+        validate_threshold(threshold)?;
+    }
+
+    // migrations run in order, each one upgrades the storage by a single version
+    if stored_version < Version::new(0, 2, 0) {
+        migrate_from_v0_1(deps.branch(), env, &msg)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+fn parse_version(version: &str) -> StdResult<Version> {
+    Version::parse(version).map_err(|e| StdError::generic_err(e.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
-        QueryMsg::State { block_time } => to_json_binary(&query_state(deps, block_time)?),
+        QueryMsg::Ownership {} => to_json_binary(&query_ownership(deps)?),
+        QueryMsg::Pause {} => to_json_binary(&query_pause(deps)?),
+        QueryMsg::Roles {
+            role,
+            start_after,
+            limit,
+        } => to_json_binary(&query_roles(deps, role, start_after, limit)?),
+        QueryMsg::State {
+            nft_addr,
+            block_time,
+        } => to_json_binary(&query_state(deps, nft_addr, block_time)?),
+        QueryMsg::Guilds {
+            start_after,
+            limit,
+            block_time,
+        } => to_json_binary(&query_guilds(deps, start_after, limit, block_time)?),
         QueryMsg::StakerInfo { staker } => to_json_binary(&query_staker_info(deps, staker)?),
+        QueryMsg::RewardRecipient { staker } => {
+            to_json_binary(&query_reward_recipient(deps, staker)?)
+        }
+        QueryMsg::ClaimOperators {
+            staker,
+            start_after,
+            limit,
+        } => to_json_binary(&query_claim_operators(deps, staker, start_after, limit)?),
+        QueryMsg::StakingOperators {
+            staker,
+            include_expired,
+            start_after,
+            limit,
+        } => to_json_binary(&query_staking_operators(
+            deps,
+            env,
+            staker,
+            include_expired.unwrap_or(false),
+            start_after,
+            limit,
+        )?),
+        QueryMsg::MintStats { nft_addr } => to_json_binary(&query_mint_stats(deps, nft_addr)?),
+        QueryMsg::CollectionMetadata { nft_addr } => {
+            to_json_binary(&query_collection_metadata(deps, nft_addr)?)
+        }
+        QueryMsg::TokenUri { nft_addr, token_id } => {
+            to_json_binary(&query_token_uri(deps, nft_addr, token_id)?)
+        }
+        QueryMsg::SalePhases { nft_addr } => {
+            to_json_binary(&query_sale_phases(deps, env, nft_addr)?)
+        }
+        QueryMsg::PhaseMints {
+            nft_addr,
+            phase_id,
+            address,
+        } => to_json_binary(&query_phase_mints(deps, nft_addr, phase_id, address)?),
+        QueryMsg::Claims { address } => to_json_binary(&query_claims(deps, address)?),
+        QueryMsg::Position { nft_addr, token_id } => {
+            to_json_binary(&query_position(deps, nft_addr, token_id)?)
+        }
+        QueryMsg::Multiplier { nft_addr, token_id } => {
+            to_json_binary(&query_multiplier(deps, nft_addr, token_id)?)
+        }
+        QueryMsg::Positions {
+            staker,
+            start_after,
+            limit,
+        } => to_json_binary(&query_positions(deps, staker, start_after, limit)?),
+        QueryMsg::Proposal { proposal_id } => {
+            to_json_binary(&query_proposal(deps, env, proposal_id)?)
+        }
+        QueryMsg::Proposals { start_after, limit } => {
+            to_json_binary(&query_proposals(deps, env, start_after, limit)?)
+        }
+        QueryMsg::Vote { proposal_id, voter } => {
+            to_json_binary(&query_vote(deps, proposal_id, voter)?)
+        }
+        QueryMsg::Schedules {
+            nft_addr,
+            start_after,
+            limit,
+        } => to_json_binary(&query_schedules(deps, nft_addr, start_after, limit)?),
+        QueryMsg::Solvency {} => to_json_binary(&query_solvency(deps, env)?),
+        QueryMsg::StakeAtHeight { staker, height } => {
+            to_json_binary(&query_stake_at_height(deps, staker, height)?)
+        }
+        QueryMsg::TotalStakeAtHeight { guild, height } => {
+            to_json_binary(&query_total_stake_at_height(deps, guild, height)?)
+        }
+        QueryMsg::VotingPowerAtHeight {
+            address,
+            height,
+            time,
+        } => to_json_binary(&query_voting_power_at_height(
+            deps, env, address, height, time,
+        )?),
+        QueryMsg::TotalVotingPowerAtHeight {
+            guild,
+            height,
+            time,
+        } => to_json_binary(&query_total_voting_power_at_height(
+            deps, env, guild, height, time,
+        )?),
     }
 }
 
@@ -549,61 +3085,577 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config = CONFIG.load(deps.storage)?;
 
     let resp = ConfigResponse {
-        owner: config.owner.to_string(),
-        scientist_nft_addr: config.scientist_nft_addr.to_string(),
-        ninja_nft_addr: config.ninja_nft_addr.to_string(),
+        owner: OWNER.get(deps)?.map(|owner| owner.to_string()),
         qtum_addr: config.qtum_addr.to_string(),
         xqtum_addr: config.xqtum_addr.to_string(),
         nft_price: config.nft_price,
-        ninja_distribution_schedule: config.ninja_distribution_schedule,
-        scientist_distribution_schedule: config.scientist_distribution_schedule,
+        native_nft_price: config.native_nft_price,
+        usdt_denom: config.usdt_denom,
+        voting_period: config.voting_period,
+        quorum: config.quorum,
+        threshold: config.threshold,
+        lock_tiers: config.lock_tiers,
+        unbonding_period: config.unbonding_period,
     };
 
     Ok(resp)
 }
 
-pub fn query_state(deps: Deps, block_time: Option<u64>) -> StdResult<StateResponse> {
-    let mut ninja_state = NINJA_GUILD.load(deps.storage)?;
-    let mut scientist_state = SCIENTIST_GUILD.load(deps.storage)?;
+pub fn query_ownership(deps: Deps) -> StdResult<OwnershipResponse> {
+    let pending = PENDING_OWNER.may_load(deps.storage)?;
+
+    Ok(OwnershipResponse {
+        owner: OWNER.get(deps)?.map(|owner| owner.to_string()),
+        pending_owner: pending.as_ref().map(|pending| pending.owner.to_string()),
+        pending_expiry: pending.map(|pending| pending.expiry),
+    })
+}
+
+pub fn query_pause(deps: Deps) -> StdResult<PauseResponse> {
+    let PauseState {
+        mint,
+        lock,
+        stake,
+        unstake,
+        withdraw,
+    } = pause_state(deps.storage)?;
+
+    Ok(PauseResponse {
+        mint,
+        lock,
+        stake,
+        unstake,
+        withdraw,
+    })
+}
+
+pub fn query_roles(
+    deps: Deps,
+    role: Role,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<RolesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let members = ROLES
+        .prefix(role.as_str())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|addr| addr.map(|addr| addr.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RolesResponse { role, members })
+}
+
+pub fn query_state(
+    deps: Deps,
+    nft_addr: String,
+    block_time: Option<u64>,
+) -> StdResult<StateResponse> {
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let guild_state = guild_state(deps, &nft_addr)?;
+
+    guild_state_to_response(deps, nft_addr, guild_state, block_time)
+}
+
+pub fn query_guilds(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    block_time: Option<u64>,
+) -> StdResult<GuildsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let guilds = GUILDS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (nft_addr, guild_state) = item?;
+            guild_state_to_response(deps, nft_addr, guild_state, block_time)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(GuildsResponse { guilds })
+}
 
+fn guild_state_to_response(
+    deps: Deps,
+    nft_addr: Addr,
+    mut guild_state: GuildState,
+    block_time: Option<u64>,
+) -> StdResult<StateResponse> {
     if let Some(block_time) = block_time {
-        let config = CONFIG.load(deps.storage)?;
-        compute_reward(
-            &config,
-            &mut ninja_state,
-            block_time,
-            config.ninja_nft_addr.to_string(),
-        );
-        compute_reward(
-            &config,
-            &mut scientist_state,
-            block_time,
-            config.scientist_nft_addr.to_string(),
-        );
+        distribute_reward(deps.storage, &nft_addr, &mut guild_state, block_time)?;
     }
 
     Ok(StateResponse {
-        ninja_total_staker: ninja_state.total_staker,
-        ninja_total_stake_amount: ninja_state.total_stake_amount,
-        ninja_last_distributed: ninja_state.last_distributed,
-        ninja_global_reward_index: ninja_state.global_reward_index,
-        scientist_total_staker: scientist_state.total_staker,
-        scientist_total_stake_amount: scientist_state.total_stake_amount,
-        scientist_last_distributed: scientist_state.last_distributed,
-        scientist_global_reward_index: scientist_state.global_reward_index,
+        nft_addr: nft_addr.to_string(),
+        total_staker: guild_state.total_staker,
+        total_stake_amount: guild_state.total_stake_amount,
+        total_effective_stake: guild_state.total_effective_stake,
+        total_voting_power: guild_state.total_voting_power,
+        last_distributed: guild_state.last_distributed,
+        rewards: guild_state.rewards,
+    })
+}
+
+pub fn query_schedules(
+    deps: Deps,
+    nft_addr: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SchedulesResponse> {
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let schedules = schedules()
+        .idx
+        .guild
+        .prefix(nft_addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(id, schedule)| ScheduleResponse {
+                id,
+                nft_addr: schedule.nft_addr.to_string(),
+                funder: schedule.funder.to_string(),
+                asset: schedule.asset,
+                start_date: schedule.start_date,
+                end_date: schedule.end_date,
+                amount: schedule.amount,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(SchedulesResponse { schedules })
+}
+
+// compares the scheduled but unclaimed rewards of every guild with the contract balance
+pub fn query_solvency(deps: Deps, env: Env) -> StdResult<SolvencyResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut assets: Vec<AssetSolvency> = vec![];
+    // unbonding xqtum is owed to the stakers as well
+    let mut total_stake_amount = TOTAL_UNBONDING.may_load(deps.storage)?.unwrap_or_default();
+
+    for item in GUILDS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, guild_state) = item?;
+        total_stake_amount += guild_state.total_stake_amount;
+
+        for reward in guild_state.rewards {
+            let committed = reward.total_rewards_distributed - reward.total_rewards_claimed;
+            match assets.iter_mut().find(|a| a.asset == reward.asset) {
+                Some(solvency) => solvency.committed += committed,
+                None => assets.push(AssetSolvency {
+                    asset: reward.asset,
+                    committed,
+                    balance: Uint128::zero(),
+                    solvent: false,
+                }),
+            }
+        }
+    }
+
+    for solvency in assets.iter_mut() {
+        solvency.balance = match &solvency.asset {
+            AssetInfo::Native { denom } => {
+                deps.querier
+                    .query_balance(&env.contract.address, denom)?
+                    .amount
+            }
+            AssetInfo::Cw20 { contract_addr } => {
+                let balance: Cw20BalanceResponse = deps.querier.query_wasm_smart(
+                    contract_addr,
+                    &Cw20QueryMsg::Balance {
+                        address: env.contract.address.to_string(),
+                    },
+                )?;
+
+                // staked xqtum is not available for rewards
+                if *contract_addr == config.xqtum_addr {
+                    balance.balance.saturating_sub(total_stake_amount)
+                } else {
+                    balance.balance
+                }
+            }
+        };
+        solvency.solvent = solvency.balance >= solvency.committed;
+    }
+
+    Ok(SolvencyResponse { assets })
+}
+
+pub fn query_reward_recipient(deps: Deps, staker: String) -> StdResult<RewardRecipientResponse> {
+    let staker = deps.api.addr_validate(&staker)?;
+
+    Ok(RewardRecipientResponse {
+        recipient: reward_recipient(deps.storage, &staker)?.to_string(),
+    })
+}
+
+pub fn query_claim_operators(
+    deps: Deps,
+    staker: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ClaimOperatorsResponse> {
+    let staker = deps.api.addr_validate(&staker)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let operators = CLAIM_OPERATORS
+        .prefix(&staker)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|addr| addr.map(|addr| addr.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ClaimOperatorsResponse { operators })
+}
+
+pub fn query_staking_operators(
+    deps: Deps,
+    env: Env,
+    staker: String,
+    include_expired: bool,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<StakingOperatorsResponse> {
+    let staker = deps.api.addr_validate(&staker)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let operators = STAKING_OPERATORS
+        .prefix(&staker)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| {
+            include_expired
+                || item
+                    .as_ref()
+                    .map_or(true, |(_, expires)| !expires.is_expired(&env.block))
+        })
+        .take(limit)
+        .map(|item| {
+            item.map(|(operator, expires)| StakingOperator {
+                operator: operator.to_string(),
+                expires,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(StakingOperatorsResponse { operators })
+}
+
+pub fn query_mint_stats(deps: Deps, nft_addr: String) -> StdResult<MintStatsResponse> {
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let collection = COLLECTIONS.load(deps.storage, &nft_addr)?;
+
+    Ok(MintStatsResponse {
+        nft_addr: nft_addr.to_string(),
+        max_supply: collection.max_supply,
+        minted: collection.minted,
+        remaining: collection.max_supply - collection.minted,
+        id_mode: collection.id_mode,
+        seed: collection.seed,
+    })
+}
+
+pub fn query_collection_metadata(
+    deps: Deps,
+    nft_addr: String,
+) -> StdResult<CollectionMetadataResponse> {
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let metadata = COLLECTION_METADATA.load(deps.storage, &nft_addr)?;
+    let revealed = metadata.revealed;
+
+    Ok(CollectionMetadataResponse {
+        nft_addr: nft_addr.to_string(),
+        base_token_uri: metadata.base_token_uri.filter(|_| revealed),
+        placeholder_uri: metadata.placeholder_uri,
+        revealed,
+        template: metadata.template,
+    })
+}
+
+pub fn query_token_uri(
+    deps: Deps,
+    nft_addr: String,
+    token_id: String,
+) -> StdResult<TokenUriResponse> {
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let token_uri = COLLECTION_METADATA
+        .may_load(deps.storage, &nft_addr)?
+        .and_then(|metadata| metadata.revealed_token_uri(&token_id));
+
+    Ok(TokenUriResponse { token_uri })
+}
+
+pub fn query_sale_phases(deps: Deps, env: Env, nft_addr: String) -> StdResult<SalePhasesResponse> {
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let phases = SALE_PHASES
+        .may_load(deps.storage, &nft_addr)?
+        .unwrap_or_default();
+    let active = phases
+        .iter()
+        .find(|phase| phase.is_active(env.block.time.seconds()))
+        .map(|phase| phase.id);
+
+    Ok(SalePhasesResponse { phases, active })
+}
+
+pub fn query_phase_mints(
+    deps: Deps,
+    nft_addr: String,
+    phase_id: u64,
+    address: String,
+) -> StdResult<PhaseMintsResponse> {
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let address = deps.api.addr_validate(&address)?;
+
+    Ok(PhaseMintsResponse {
+        minted: PHASE_MINTS
+            .may_load(deps.storage, (&nft_addr, phase_id, &address))?
+            .unwrap_or_default(),
     })
 }
 
+pub fn query_claims(deps: Deps, address: String) -> StdResult<ClaimsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+
+    CLAIMS.query_claims(deps, &address)
+}
+
+// totals over every position of the staker
 pub fn query_staker_info(deps: Deps, staker: String) -> StdResult<StakerInfoResponse> {
     let staker = deps.api.addr_validate(&staker)?;
 
     let user_info: StakerInfo = user_staking(deps, staker.as_str())?;
 
+    let mut rewards: Vec<StakerRewardResponse> = vec![];
+    for position in staker_positions(deps.storage, &staker)? {
+        for reward in position.rewards {
+            match rewards.iter_mut().find(|r| r.asset == reward.asset) {
+                Some(total) => {
+                    total.pending_rewards += reward.pending_rewards;
+                    total.reward_claimed += reward.reward_claimed;
+                }
+                None => rewards.push(StakerRewardResponse {
+                    asset: reward.asset,
+                    pending_rewards: reward.pending_rewards,
+                    reward_claimed: reward.reward_claimed,
+                }),
+            }
+        }
+    }
+
     Ok(StakerInfoResponse {
         stake_amount: user_info.stake_amount,
-        pending_rewards: user_info.pending_rewards,
-        reward_claimed: user_info.reward_claimed,
-        nft_addr: user_info.nft_addr,
-        token_id: user_info.token_id,
+        voting_power: user_info.voting_power,
+        positions: user_info.positions,
+        auto_compound: AUTO_COMPOUND.has(deps.storage, &staker),
+        rewards,
+    })
+}
+
+pub fn query_position(
+    deps: Deps,
+    nft_addr: String,
+    token_id: String,
+) -> StdResult<PositionResponse> {
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    let position = positions().load(deps.storage, (&nft_addr, &token_id))?;
+    Ok(position_to_response(position))
+}
+
+pub fn query_multiplier(
+    deps: Deps,
+    nft_addr: String,
+    token_id: String,
+) -> StdResult<MultiplierResponse> {
+    let nft_addr = deps.api.addr_validate(&nft_addr)?;
+    Ok(MultiplierResponse {
+        multiplier: nft_multiplier(deps, &nft_addr, &token_id)?,
+    })
+}
+
+pub fn query_positions(
+    deps: Deps,
+    staker: String,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<PositionsResponse> {
+    let staker = deps.api.addr_validate(&staker)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = match start_after {
+        Some((nft_addr, token_id)) => Some(Bound::exclusive((
+            deps.api.addr_validate(&nft_addr)?,
+            token_id,
+        ))),
+        None => None,
+    };
+
+    let positions = positions()
+        .idx
+        .owner
+        .prefix(staker)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, position)| position_to_response(position)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PositionsResponse { positions })
+}
+
+fn position_to_response(position: Position) -> PositionResponse {
+    PositionResponse {
+        owner: position.owner.to_string(),
+        nft_addr: position.nft_addr.to_string(),
+        effective_stake: position.effective_stake(),
+        voting_power: position.voting_power(),
+        token_id: position.token_id,
+        stake_amount: position.stake_amount,
+        multiplier: position.multiplier,
+        locks: position.locks,
+        rewards: position
+            .rewards
+            .into_iter()
+            .map(|reward| StakerRewardResponse {
+                asset: reward.asset,
+                pending_rewards: reward.pending_rewards,
+                reward_claimed: reward.reward_claimed,
+            })
+            .collect(),
+    }
+}
+
+pub fn query_proposal(deps: Deps, env: Env, proposal_id: u64) -> StdResult<ProposalResponse> {
+    let proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+    Ok(proposal_to_response(&env, proposal_id, proposal))
+}
+
+pub fn query_proposals(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let proposals = PROPOSALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, proposal)| proposal_to_response(&env, id, proposal)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProposalsResponse { proposals })
+}
+
+pub fn query_vote(deps: Deps, proposal_id: u64, voter: String) -> StdResult<VoteResponse> {
+    let voter = deps.api.addr_validate(&voter)?;
+    let ballot = BALLOTS.may_load(deps.storage, (proposal_id, &voter))?;
+
+    Ok(match ballot {
+        Some(ballot) => VoteResponse {
+            vote: Some(ballot.vote),
+            weight: ballot.weight,
+        },
+        None => VoteResponse {
+            vote: None,
+            weight: Uint128::zero(),
+        },
+    })
+}
+
+pub fn query_stake_at_height(
+    deps: Deps,
+    staker: String,
+    height: u64,
+) -> StdResult<StakeAtHeightResponse> {
+    let staker = deps.api.addr_validate(&staker)?;
+
+    Ok(StakeAtHeightResponse {
+        stake_amount: stake_at_height(deps.storage, staker.as_str(), height)?,
+        height,
+    })
+}
+
+pub fn query_total_stake_at_height(
+    deps: Deps,
+    guild: String,
+    height: u64,
+) -> StdResult<TotalStakeAtHeightResponse> {
+    let guild = deps.api.addr_validate(&guild)?;
+
+    Ok(TotalStakeAtHeightResponse {
+        total_stake_amount: total_stake_at_height(deps.storage, &guild, height)?,
+        height,
+    })
+}
+
+fn proposal_to_response(env: &Env, id: u64, proposal: Proposal) -> ProposalResponse {
+    ProposalResponse {
+        id,
+        status: proposal.current_status(env.block.time.seconds()),
+        proposer: proposal.proposer.to_string(),
+        title: proposal.title,
+        description: proposal.description,
+        msgs: proposal.msgs,
+        start_height: proposal.start_height,
+        start_time: proposal.start_time,
+        expires: proposal.expires,
+        quorum: proposal.quorum,
+        threshold: proposal.threshold,
+        total_power: proposal.total_power,
+        yes_votes: proposal.yes_votes,
+        no_votes: proposal.no_votes,
+        abstain_votes: proposal.abstain_votes,
+    }
+}
+
+pub fn query_voting_power_at_height(
+    deps: Deps,
+    env: Env,
+    address: String,
+    height: u64,
+    time: Option<u64>,
+) -> StdResult<VotingPowerAtHeightResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let time = time.unwrap_or_else(|| env.block.time.seconds());
+
+    Ok(VotingPowerAtHeightResponse {
+        voting_power: voting_power_at_height(deps.storage, address.as_str(), height, time)?,
+        height,
+    })
+}
+
+pub fn query_total_voting_power_at_height(
+    deps: Deps,
+    env: Env,
+    guild: String,
+    height: u64,
+    time: Option<u64>,
+) -> StdResult<TotalVotingPowerAtHeightResponse> {
+    let guild = deps.api.addr_validate(&guild)?;
+    let time = time.unwrap_or_else(|| env.block.time.seconds());
+
+    Ok(TotalVotingPowerAtHeightResponse {
+        total_voting_power: total_voting_power_at_height(deps.storage, &guild, height, time)?,
+        height,
     })
 }
@@ -1,4 +1,5 @@
 use cosmwasm_std::StdError;
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,6 +7,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -18,6 +22,153 @@ pub enum ContractError {
     #[error("NFT is not supported")]
     UnsupportedNft {},
 
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Contract is not paused")]
+    NotPaused {},
+
+    #[error("Multiplier must be greater than zero")]
+    InvalidMultiplier {},
+
+    #[error("Lock duration does not match a lock tier")]
+    InvalidLockDuration {},
+
+    #[error("Lock tiers need distinct non zero durations and multipliers of at least one")]
+    InvalidLockTier {},
+
+    #[error("Stake is locked")]
+    StakeLocked {},
+
+    #[error("Auto compounding is not enabled")]
+    AutoCompoundDisabled {},
+
+    #[error("Approval has expired")]
+    Expired {},
+
+    #[error("Collection is sold out")]
+    SoldOut {},
+
+    #[error("Max supply is below the minted amount")]
+    InvalidMaxSupply {},
+
+    #[error("Id mode, shuffled supply and base token uri cannot change after the first mint")]
+    CollectionLocked {},
+
+    #[error("Token id has to be sent only when the minter chooses it")]
+    InvalidTokenId {},
+
+    #[error("Collection is already revealed")]
+    AlreadyRevealed {},
+
+    #[error("Seed hash has to be a 32 byte hex sha256")]
+    InvalidSeedHash {},
+
+    #[error("Seed does not match the committed hash")]
+    InvalidSeed {},
+
+    #[error("Shuffled ids need the revealed seed")]
+    SeedNotRevealed {},
+
+    #[error("Collection has no base token uri")]
+    MissingBaseTokenUri {},
+
+    #[error("Minting with native coins is disabled")]
+    NativeMintDisabled {},
+
+    #[error("Native nft price can't be set while disabling native mints")]
+    NativeMintConflict {},
+
+    #[error("Sale is closed")]
+    SaleClosed {},
+
+    #[error("Sale phases need distinct ids, start before end, no overlaps and a 32 byte hex root for allowlists")]
+    InvalidSalePhase {},
+
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof {},
+
+    #[error("Mint cap of the phase reached")]
+    MintCapReached {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Position not found")]
+    PositionNotFound {},
+
+    #[error("Attached funds must match the schedule amount")]
+    ScheduleFundsMismatch {},
+
+    #[error("Schedule start date must be before its end date")]
+    InvalidScheduleRange {},
+
+    #[error("Schedule can not start in the past")]
+    ScheduleStartInPast {},
+
+    #[error("Schedule amount must be greater than zero")]
+    ZeroScheduleAmount {},
+
+    #[error("Distribution schedule has already ended")]
+    ScheduleFinished {},
+
+    #[error("Guild already exists")]
+    GuildAlreadyExists {},
+
+    #[error("Guild still has locked nfts")]
+    GuildNotEmpty {},
+
+    #[error("Xqtum is still staked or unbonding")]
+    StakeOutstanding {},
+
+    #[error("Guild still has distribution schedules")]
+    GuildHasSchedules {},
+
+    #[error("Invalid denom: {denom}")]
+    InvalidDenom { denom: String },
+
+    #[error("Nft price must be greater than zero")]
+    ZeroNftPrice {},
+
+    #[error("Voting period must be greater than zero")]
+    ZeroVotingPeriod {},
+
+    #[error("Quorum and threshold must be greater than 0 and at most 1")]
+    InvalidThreshold {},
+
+    #[error("No voting power")]
+    NoVotingPower {},
+
+    #[error("Proposal is not open for voting")]
+    ProposalNotOpen {},
+
+    #[error("Already voted on this proposal")]
+    AlreadyVoted {},
+
+    #[error("Only passed proposals can be executed")]
+    WrongExecuteStatus {},
+
+    #[error("Only rejected proposals can be closed")]
+    WrongCloseStatus {},
+
+    #[error("Role has not been granted to this address")]
+    RoleNotGranted {},
+
+    #[error("No pending ownership proposal")]
+    NoPendingOwner {},
+
+    #[error("Ownership proposal has expired")]
+    OwnershipProposalExpired {},
+
+    #[error("Cannot migrate from contract {name}")]
+    InvalidContractName { name: String },
+
+    #[error("Cannot migrate from newer version {stored} to {current}")]
+    CannotDowngrade { stored: String, current: String },
+
+    #[error("Voting period, quorum and threshold are required for this migration")]
+    MissingMigrationParams {},
+
     #[error("Custom Error val: {msg:?}")]
     CustomError { msg: String },
     // Add any other custom errors you like here.
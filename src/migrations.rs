@@ -0,0 +1,231 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, DepsMut, Env, Order, StdResult, Uint128};
+use cw_storage_plus::{Item, Map};
+
+use crate::error::ContractError;
+use crate::msg::MigrateMsg;
+use crate::state::{
+    positions, schedules, store_guild_state, store_user_staking, AssetInfo, Collection, Config,
+    GuildState, Position, RewardState, SaleKind, SalePhase, Schedule, StakerInfo, StakerReward,
+    TokenIdMode, COLLECTIONS, CONFIG, OWNER, SALE_PHASES, SCHEDULE_COUNT,
+};
+
+// storage layout of v0.1.0
+#[cw_serde]
+struct ConfigV0_1 {
+    owner: Addr,
+    scientist_nft_addr: Addr,
+    ninja_nft_addr: Addr,
+    qtum_addr: Addr,
+    xqtum_addr: Addr,
+    nft_price: Uint128,
+    usdt_denom: String,
+    ninja_distribution_schedule: Vec<(u64, u64, Uint128)>,
+    scientist_distribution_schedule: Vec<(u64, u64, Uint128)>,
+}
+
+#[cw_serde]
+struct GuildStateV0_1 {
+    total_rewards_distributed: Uint128,
+    total_staker: u64,
+    total_stake_amount: Uint128,
+    last_distributed: u64,
+    global_reward_index: Decimal,
+}
+
+#[cw_serde]
+struct StakerInfoV0_1 {
+    stake_amount: Uint128,
+    pending_rewards: Uint128,
+    reward_index: Decimal,
+    reward_claimed: Uint128,
+    nft_addr: Option<String>,
+    token_id: Option<String>,
+}
+
+const CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+const NINJA_GUILD_V0_1: Item<GuildStateV0_1> = Item::new("ninja_state");
+const SCIENTIST_GUILD_V0_1: Item<GuildStateV0_1> = Item::new("scientist_state");
+const USER_STAKING_V0_1: Map<&str, StakerInfoV0_1> = Map::new("ninja_user_info");
+
+// the two guild items become GUILDS entries, the config schedules move to schedules()
+// and staker rewards are tracked per asset. both guild collections stay mintable the way
+// v0.1.0 minted them: chosen ids, no supply cap and a public sale at the config prices
+pub fn migrate_from_v0_1(
+    mut deps: DepsMut,
+    env: Env,
+    msg: &MigrateMsg,
+) -> Result<(), ContractError> {
+    let (voting_period, quorum, threshold) = match (msg.voting_period, msg.quorum, msg.threshold) {
+        (Some(voting_period), Some(quorum), Some(threshold)) => (voting_period, quorum, threshold),
+        _ => return Err(ContractError::MissingMigrationParams {}),
+    };
+
+    let old_config = CONFIG_V0_1.load(deps.storage)?;
+    OWNER.set(deps.branch(), Some(old_config.owner.clone()))?;
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            qtum_addr: old_config.qtum_addr,
+            xqtum_addr: old_config.xqtum_addr,
+            nft_price: old_config.nft_price,
+            native_nft_price: None,
+            usdt_denom: old_config.usdt_denom.clone(),
+            voting_period,
+            quorum,
+            threshold,
+            lock_tiers: vec![],
+            unbonding_period: 0,
+        },
+    )?;
+
+    // v0.1.0 paid ninja rewards in inj and scientist rewards in usdt
+    let guilds = vec![
+        (
+            old_config.ninja_nft_addr,
+            NINJA_GUILD_V0_1.load(deps.storage)?,
+            old_config.ninja_distribution_schedule,
+            AssetInfo::Native {
+                denom: "inj".to_string(),
+            },
+        ),
+        (
+            old_config.scientist_nft_addr,
+            SCIENTIST_GUILD_V0_1.load(deps.storage)?,
+            old_config.scientist_distribution_schedule,
+            AssetInfo::Native {
+                denom: old_config.usdt_denom,
+            },
+        ),
+    ];
+
+    let stakers = USER_STAKING_V0_1
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    // the namespace is reused for the staker totals
+    for (staker, _) in stakers.iter() {
+        USER_STAKING_V0_1.remove(deps.storage, staker);
+    }
+
+    let mut schedule_id = SCHEDULE_COUNT.may_load(deps.storage)?.unwrap_or_default();
+
+    for (nft_addr, old_state, old_schedules, asset) in guilds {
+        // rewards still owed to the guild: the unreleased schedule amounts plus
+        // whatever the stakers have accrued and not claimed yet
+        let mut unreleased = Uint128::zero();
+        for (start_date, end_date, amount) in old_schedules {
+            // the config schedules were funded by the owner
+            let schedule = Schedule {
+                nft_addr: nft_addr.clone(),
+                funder: old_config.owner.clone(),
+                asset: asset.clone(),
+                start_date,
+                end_date,
+                amount,
+            };
+            unreleased += amount - schedule.released_amount(old_state.last_distributed);
+
+            schedule_id += 1;
+            schedules().save(deps.storage, schedule_id, &schedule)?;
+        }
+
+        let mut accrued = Uint128::zero();
+        for (staker, old_info) in stakers.iter() {
+            if old_info.nft_addr.as_deref() != Some(nft_addr.as_str()) {
+                continue;
+            }
+            let token_id = match &old_info.token_id {
+                Some(token_id) => token_id,
+                None => continue,
+            };
+
+            let pending_rewards = old_info.pending_rewards
+                + (old_info.stake_amount * old_state.global_reward_index)
+                    .saturating_sub(old_info.stake_amount * old_info.reward_index);
+            accrued += pending_rewards;
+
+            // v0.1.0 stakers had a single nft, it becomes their only position
+            positions().save(
+                deps.storage,
+                (&nft_addr, token_id),
+                &Position {
+                    owner: deps.api.addr_validate(staker)?,
+                    nft_addr: nft_addr.clone(),
+                    token_id: token_id.clone(),
+                    stake_amount: old_info.stake_amount,
+                    multiplier: Decimal::one(),
+                    locks: vec![],
+                    rewards: vec![StakerReward {
+                        asset: asset.clone(),
+                        pending_rewards,
+                        reward_index: old_state.global_reward_index,
+                        reward_claimed: old_info.reward_claimed,
+                    }],
+                },
+            )?;
+            store_user_staking(
+                deps.storage,
+                staker,
+                &StakerInfo {
+                    stake_amount: old_info.stake_amount,
+                    voting_power: old_info.stake_amount,
+                    positions: 1,
+                    lock_bonuses: vec![],
+                },
+                env.block.height,
+            )?;
+        }
+
+        store_guild_state(
+            deps.storage,
+            &GuildState {
+                total_staker: old_state.total_staker,
+                total_stake_amount: old_state.total_stake_amount,
+                total_effective_stake: old_state.total_stake_amount,
+                total_voting_power: old_state.total_stake_amount,
+                last_distributed: old_state.last_distributed,
+                rewards: vec![RewardState {
+                    asset,
+                    total_rewards_distributed: old_state.total_rewards_distributed,
+                    total_rewards_claimed: old_state
+                        .total_rewards_distributed
+                        .saturating_sub(unreleased + accrued),
+                    global_reward_index: old_state.global_reward_index,
+                }],
+            },
+            &nft_addr,
+            env.block.height,
+        )?;
+
+        COLLECTIONS.save(
+            deps.storage,
+            &nft_addr,
+            &Collection {
+                max_supply: u64::MAX,
+                minted: 0,
+                id_mode: TokenIdMode::Chosen,
+                seed: None,
+            },
+        )?;
+        SALE_PHASES.save(
+            deps.storage,
+            &nft_addr,
+            &vec![SalePhase {
+                id: 1,
+                kind: SaleKind::Public,
+                start: env.block.time.seconds(),
+                end: u64::MAX,
+                price: None,
+                native_price: None,
+                per_address_cap: None,
+            }],
+        )?;
+    }
+
+    SCHEDULE_COUNT.save(deps.storage, &schedule_id)?;
+    NINJA_GUILD_V0_1.remove(deps.storage);
+    SCIENTIST_GUILD_V0_1.remove(deps.storage);
+
+    Ok(())
+}
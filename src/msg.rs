@@ -1,55 +1,312 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_schema::serde::{Deserialize, Serialize};
+use cosmwasm_std::{Coin, CosmosMsg, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
 use cw721::Cw721ReceiveMsg;
+use cw_utils::Expiration;
 
-use crate::state::Config;
+use crate::state::{
+    AssetInfo, LockTier, MetadataTemplate, MetadataTrait, ProposalStatus, RewardState, Role,
+    SalePhase, StakeLock, TokenIdMode, VoteOption,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub scientist_nft_addr: String,
-    pub ninja_nft_addr: String,
-
     pub qtum_addr: String,
     pub xqtum_addr: String,
 
     pub usdt_denom: String,
 
     pub nft_price: Uint128,
+    pub native_nft_price: Option<Coin>,
+
+    pub voting_period: u64,
+    pub quorum: Decimal,
+    pub threshold: Decimal,
+
+    pub lock_tiers: Vec<LockTier>,
+
+    pub unbonding_period: u64,
+}
+
+// governance params are required when migrating from v0.1.0
+#[cw_serde]
+pub struct MigrateMsg {
+    pub voting_period: Option<u64>,
+    pub quorum: Option<Decimal>,
+    pub threshold: Option<Decimal>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
     ReceiveNft(Cw721ReceiveMsg),
+    // positions are identified by the nft address and token id of the locked nft
     Unlock {
-        id: String,
+        nft_addr: String,
+        token_id: String,
     },
     Unstake {
+        nft_addr: String,
+        token_id: String,
         amount: Uint128,
     },
-    // withdraw pending rewards
-    Withdraw {},
+    // mint paid with the native nft price, the excess is refunded
+    Mint {
+        id: Option<String>,
+        nft_addr: String,
+        proof: Option<Vec<String>>,
+    },
+    // withdraw pending rewards of every position, recipient defaults to the
+    // reward recipient of the staker
+    Withdraw {
+        recipient: Option<String>,
+    },
+    // a none recipient pays the rewards to the staker again
+    SetRewardRecipient {
+        recipient: Option<String>,
+    },
+    // claim operators can withdraw for the staker, always to its reward recipient
+    ApproveClaimOperator {
+        operator: String,
+    },
+    RevokeClaimOperator {
+        operator: String,
+    },
+    WithdrawFor {
+        staker: String,
+    },
+    // staking operators can stake, unstake, withdraw and vote for the staker,
+    // the unstaked xqtum and the rewards are always paid to the staker
+    ApproveOperator {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    RevokeOperator {
+        operator: String,
+    },
+    // transfer the xqtum whose unbonding period has passed
+    ClaimUnbonded {},
+    // stake the pending xqtum rewards on the positions they accrued on. only rewards paid in
+    // xqtum are compounded, there is no conversion: rewards in other assets stay pending
+    // for Withdraw and a staker without xqtum rewards gets NothingToClaim
+    Compound {},
+    SetAutoCompound {
+        enabled: bool,
+    },
+    // callable by anyone for stakers that enabled auto compounding
+    AutoCompound {
+        staker: String,
+    },
+    // asset defaults to the guild's default reward asset, the native reward amount has to be attached
     SetDistribution {
         nft_addr: String,
+        asset: Option<AssetInfo>,
         start_date: u64,
         end_date: u64,
         amount: Uint128,
     },
+    // only the given fields are updated, xqtum_addr can't change while xqtum is staked
+    // or unbonding. disable_native_mint clears the native nft price
     UpdateConfig {
-        config: Config,
+        qtum_addr: Option<String>,
+        xqtum_addr: Option<String>,
+        nft_price: Option<Uint128>,
+        native_nft_price: Option<Coin>,
+        disable_native_mint: Option<bool>,
+        usdt_denom: Option<String>,
+        voting_period: Option<u64>,
+        quorum: Option<Decimal>,
+        threshold: Option<Decimal>,
+        lock_tiers: Option<Vec<LockTier>>,
+        unbonding_period: Option<u64>,
+    },
+    // guild registry, reward asset defaults to the usdt_denom native token
+    AddGuild {
+        nft_addr: String,
+        reward_asset: Option<AssetInfo>,
+    },
+    RemoveGuild {
+        nft_addr: String,
+    },
+    // schedule management, the unreleased amount of a cancelled schedule is refunded
+    CancelSchedule {
+        schedule_id: u64,
+    },
+    UpdateSchedule {
+        schedule_id: u64,
+        end_date: u64,
+    },
+    PruneSchedules {
+        nft_addr: String,
+    },
+    // registers a guild collection for minting or updates its supply,
+    // the id mode and a shuffled supply are fixed after the first mint
+    SetCollection {
+        nft_addr: String,
+        max_supply: u64,
+        id_mode: TokenIdMode,
+    },
+    // reveals the seed committed for the shuffled ids, minting is closed until then
+    RevealSeed {
+        nft_addr: String,
+        seed: String,
+    },
+    // token uri and metadata template of the collection, the reveal state is kept. the base
+    // token uri is fixed once the first token is minted
+    SetCollectionMetadata {
+        nft_addr: String,
+        base_token_uri: Option<String>,
+        placeholder_uri: Option<String>,
+        template: Option<MetadataTemplate>,
+    },
+    // marks the collection as revealed, can't be undone. the token uri query switches from the
+    // placeholder uri to the base token uri, the cw721 token uris are set on mint already
+    Reveal {
+        nft_addr: String,
+    },
+    // replaces the sale phases of the collection, phases may not overlap
+    SetSalePhases {
+        nft_addr: String,
+        phases: Vec<SalePhase>,
+    },
+    // multiplier tables of a guild, a none multiplier removes the entry
+    SetTokenMultipliers {
+        nft_addr: String,
+        multipliers: Vec<TokenMultiplier>,
+    },
+    SetTraitMultipliers {
+        nft_addr: String,
+        multipliers: Vec<TraitMultiplier>,
+    },
+    // governance
+    Propose {
+        title: String,
+        description: String,
+        msgs: Vec<CosmosMsg>,
+    },
+    Vote {
+        proposal_id: u64,
+        vote: VoteOption,
+    },
+    VoteFor {
+        proposal_id: u64,
+        vote: VoteOption,
+        staker: String,
+    },
+    Execute {
+        proposal_id: u64,
+    },
+    Close {
+        proposal_id: u64,
+    },
+    // two step ownership transfer, the proposal never expires without an expiry
+    ProposeNewOwner {
+        new_owner: String,
+        expiry: Option<Expiration>,
+    },
+    AcceptOwnership {},
+    CancelOwnershipProposal {},
+    // also revokes every admin grant
+    RenounceOwnership {},
+    // circuit breaker, only the given switches are changed
+    SetPause {
+        mint: Option<bool>,
+        lock: Option<bool>,
+        stake: Option<bool>,
+        unstake: Option<bool>,
+        withdraw: Option<bool>,
+    },
+    // roles are managed by admins, the admin role by the owner
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    // returns the locked nft and the staked xqtum without unbonding while unstake and withdraw
+    // are paused, running stake locks are released and pending rewards are forfeited
+    EmergencyUnlock {
+        nft_addr: String,
+        token_id: String,
+    },
+    // settles the position and removes its expired stake locks, callable by anyone. the boost
+    // of a lock already ends at its end for the guild totals and governance
+    ExpireLocks {
+        nft_addr: String,
+        token_id: String,
     },
 }
 
+#[cw_serde]
+pub struct TokenMultiplier {
+    pub token_id: String,
+    pub multiplier: Option<Decimal>,
+}
+
+#[cw_serde]
+pub struct TraitMultiplier {
+    pub trait_type: String,
+    pub value: String,
+    pub multiplier: Option<Decimal>,
+}
+
 #[cw_serde]
 pub enum Cw20HookMsg {
-    Mint { id: String, nft_addr: String },
-    Stake {},
+    // proof is required during allowlist phases, id only for collections
+    // where the minter chooses the token id. the excess is refunded
+    Mint {
+        id: Option<String>,
+        nft_addr: String,
+        proof: Option<Vec<String>>,
+    },
+    // stake on the position of the locked nft, lock_duration has to match a lock tier
+    Stake {
+        nft_addr: String,
+        token_id: String,
+        lock_duration: Option<u64>,
+    },
+    // distribution schedule paid in the sent cw20 token
+    FundSchedule {
+        nft_addr: String,
+        start_date: u64,
+        end_date: u64,
+    },
 }
 
 #[cw_serde]
 pub enum Cw721HookMsg {
-    Lock { id: String },
+    Lock {},
+}
+
+// the part of the cw721 metadata extension used for the trait multipliers,
+// unknown fields are ignored so any metadata extension can be read
+#[derive(Deserialize, Default)]
+#[serde(crate = "cosmwasm_schema::serde")]
+pub struct NftMetadata {
+    pub attributes: Option<Vec<NftTrait>>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "cosmwasm_schema::serde")]
+pub struct NftTrait {
+    pub trait_type: String,
+    pub value: String,
+}
+
+// the metadata extension sent on mint. unset fields are left out so collections
+// without an extension accept it as well
+#[derive(Serialize, Default)]
+#[serde(crate = "cosmwasm_schema::serde")]
+pub struct MintExtension {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Vec<MetadataTrait>>,
 }
 
 // query msgs
@@ -57,44 +314,357 @@ pub enum Cw721HookMsg {
 #[cw_serde]
 pub enum QueryMsg {
     Config {},
-    State { block_time: Option<u64> },
-    StakerInfo { staker: String },
+    Ownership {},
+    Pause {},
+    // addresses holding the role, the owner holds every role implicitly
+    Roles {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    State {
+        nft_addr: String,
+        block_time: Option<u64>,
+    },
+    Guilds {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        block_time: Option<u64>,
+    },
+    StakerInfo {
+        staker: String,
+    },
+    // address the rewards of the staker are paid to
+    RewardRecipient {
+        staker: String,
+    },
+    ClaimOperators {
+        staker: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    StakingOperators {
+        staker: String,
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    MintStats {
+        nft_addr: String,
+    },
+    // the base token uri is hidden until the collection is revealed
+    CollectionMetadata {
+        nft_addr: String,
+    },
+    // placeholder uri until the collection is revealed, the uri the token is minted with after
+    TokenUri {
+        nft_addr: String,
+        token_id: String,
+    },
+    SalePhases {
+        nft_addr: String,
+    },
+    PhaseMints {
+        nft_addr: String,
+        phase_id: u64,
+        address: String,
+    },
+    // unbonding xqtum of the address, returns cw_controllers::ClaimsResponse
+    Claims {
+        address: String,
+    },
+    Position {
+        nft_addr: String,
+        token_id: String,
+    },
+    // multiplier the nft would get when locked now
+    Multiplier {
+        nft_addr: String,
+        token_id: String,
+    },
+    // start_after is the (nft address, token id) of the last position
+    Positions {
+        staker: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    Schedules {
+        nft_addr: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    Solvency {},
+    Proposal {
+        proposal_id: u64,
+    },
+    Proposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    Vote {
+        proposal_id: u64,
+        voter: String,
+    },
+    // stake at the beginning of the given block height
+    StakeAtHeight {
+        staker: String,
+        height: u64,
+    },
+    // guild is the nft address of the guild
+    TotalStakeAtHeight {
+        guild: String,
+        height: u64,
+    },
+    // locks ending by time, the current block time by default, no longer count
+    VotingPowerAtHeight {
+        address: String,
+        height: u64,
+        time: Option<u64>,
+    },
+    TotalVotingPowerAtHeight {
+        guild: String,
+        height: u64,
+        time: Option<u64>,
+    },
 }
 
 // We define a custom struct for each query response
 #[cw_serde]
 pub struct ConfigResponse {
-    pub owner: String,
-
-    pub scientist_nft_addr: String,
-    pub ninja_nft_addr: String,
+    // none once the ownership has been renounced
+    pub owner: Option<String>,
 
     pub qtum_addr: String,
     pub xqtum_addr: String,
 
     pub nft_price: Uint128,
+    pub native_nft_price: Option<Coin>,
+
+    pub usdt_denom: String,
+
+    pub voting_period: u64,
+    pub quorum: Decimal,
+    pub threshold: Decimal,
 
-    pub ninja_distribution_schedule: Vec<(u64, u64, Uint128)>,
-    pub scientist_distribution_schedule: Vec<(u64, u64, Uint128)>,
+    pub lock_tiers: Vec<LockTier>,
+
+    pub unbonding_period: u64,
+}
+
+#[cw_serde]
+pub struct OwnershipResponse {
+    pub owner: Option<String>,
+    pub pending_owner: Option<String>,
+    pub pending_expiry: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct PauseResponse {
+    pub mint: bool,
+    pub lock: bool,
+    pub stake: bool,
+    pub unstake: bool,
+    pub withdraw: bool,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    pub role: Role,
+    pub members: Vec<String>,
 }
 
 #[cw_serde]
 pub struct StateResponse {
-    pub ninja_total_staker: u64,
-    pub ninja_total_stake_amount: Uint128,
-    pub ninja_last_distributed: u64,
-    pub ninja_global_reward_index: Decimal,
-    pub scientist_total_staker: u64,
-    pub scientist_total_stake_amount: Uint128,
-    pub scientist_last_distributed: u64,
-    pub scientist_global_reward_index: Decimal,
+    pub nft_addr: String,
+    pub total_staker: u64,
+    pub total_stake_amount: Uint128,
+    pub total_effective_stake: Uint128,
+    pub total_voting_power: Uint128,
+    pub last_distributed: u64,
+    pub rewards: Vec<RewardState>,
+}
+
+#[cw_serde]
+pub struct GuildsResponse {
+    pub guilds: Vec<StateResponse>,
 }
 
 #[cw_serde]
 pub struct StakerInfoResponse {
     pub stake_amount: Uint128,
+    pub voting_power: Uint128,
+    pub positions: u64,
+    pub auto_compound: bool,
+    pub rewards: Vec<StakerRewardResponse>,
+}
+
+#[cw_serde]
+pub struct PositionResponse {
+    pub owner: String,
+    pub nft_addr: String,
+    pub token_id: String,
+    pub stake_amount: Uint128,
+    pub multiplier: Decimal,
+    pub effective_stake: Uint128,
+    pub voting_power: Uint128,
+    pub locks: Vec<StakeLock>,
+    pub rewards: Vec<StakerRewardResponse>,
+}
+
+#[cw_serde]
+pub struct RewardRecipientResponse {
+    pub recipient: String,
+}
+
+#[cw_serde]
+pub struct ClaimOperatorsResponse {
+    pub operators: Vec<String>,
+}
+
+#[cw_serde]
+pub struct StakingOperator {
+    pub operator: String,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub struct StakingOperatorsResponse {
+    pub operators: Vec<StakingOperator>,
+}
+
+#[cw_serde]
+pub struct MintStatsResponse {
+    pub nft_addr: String,
+    pub max_supply: u64,
+    pub minted: u64,
+    pub remaining: u64,
+    pub id_mode: TokenIdMode,
+    pub seed: Option<String>,
+}
+
+#[cw_serde]
+pub struct CollectionMetadataResponse {
+    pub nft_addr: String,
+    pub base_token_uri: Option<String>,
+    pub placeholder_uri: Option<String>,
+    pub revealed: bool,
+    pub template: Option<MetadataTemplate>,
+}
+
+#[cw_serde]
+pub struct TokenUriResponse {
+    pub token_uri: Option<String>,
+}
+
+#[cw_serde]
+pub struct SalePhasesResponse {
+    pub phases: Vec<SalePhase>,
+    // id of the phase running now
+    pub active: Option<u64>,
+}
+
+#[cw_serde]
+pub struct PhaseMintsResponse {
+    pub minted: u32,
+}
+
+#[cw_serde]
+pub struct MultiplierResponse {
+    pub multiplier: Decimal,
+}
+
+#[cw_serde]
+pub struct PositionsResponse {
+    pub positions: Vec<PositionResponse>,
+}
+
+#[cw_serde]
+pub struct StakerRewardResponse {
+    pub asset: AssetInfo,
     pub pending_rewards: Uint128,
     pub reward_claimed: Uint128,
-    pub nft_addr: Option<String>,
-    pub token_id: Option<String>,
+}
+
+#[cw_serde]
+pub struct ProposalResponse {
+    pub id: u64,
+    pub proposer: String,
+    pub title: String,
+    pub description: String,
+    pub msgs: Vec<CosmosMsg>,
+    pub status: ProposalStatus,
+    pub start_height: u64,
+    pub start_time: u64,
+    pub expires: u64,
+    pub quorum: Decimal,
+    pub threshold: Decimal,
+    pub total_power: Uint128,
+    pub yes_votes: Uint128,
+    pub no_votes: Uint128,
+    pub abstain_votes: Uint128,
+}
+
+#[cw_serde]
+pub struct ProposalsResponse {
+    pub proposals: Vec<ProposalResponse>,
+}
+
+#[cw_serde]
+pub struct VoteResponse {
+    pub vote: Option<VoteOption>,
+    pub weight: Uint128,
+}
+
+#[cw_serde]
+pub struct StakeAtHeightResponse {
+    pub stake_amount: Uint128,
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct TotalStakeAtHeightResponse {
+    pub total_stake_amount: Uint128,
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct VotingPowerAtHeightResponse {
+    pub voting_power: Uint128,
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct TotalVotingPowerAtHeightResponse {
+    pub total_voting_power: Uint128,
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct SolvencyResponse {
+    pub assets: Vec<AssetSolvency>,
+}
+
+#[cw_serde]
+pub struct AssetSolvency {
+    pub asset: AssetInfo,
+    // scheduled rewards that have not been claimed yet
+    pub committed: Uint128,
+    pub balance: Uint128,
+    pub solvent: bool,
+}
+
+#[cw_serde]
+pub struct ScheduleResponse {
+    pub id: u64,
+    pub nft_addr: String,
+    pub funder: String,
+    pub asset: AssetInfo,
+    pub start_date: u64,
+    pub end_date: u64,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct SchedulesResponse {
+    pub schedules: Vec<ScheduleResponse>,
 }
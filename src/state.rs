@@ -1,49 +1,581 @@
 use cosmwasm_schema::cw_serde;
 
-use cosmwasm_std::{Addr, Decimal, Deps, Response, StdResult, Storage, Uint128};
-use cw_storage_plus::{Item, Map};
+use std::fmt;
+
+use cosmwasm_std::{
+    to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, Empty, Order, Response,
+    StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw_controllers::{Admin, Claims};
+use cw_storage_plus::{
+    Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex, SnapshotMap, Strategy,
+};
+use cw_utils::Expiration;
 
 #[cw_serde]
 pub struct Config {
-    pub owner: Addr,
-
-    pub scientist_nft_addr: Addr,
-    pub ninja_nft_addr: Addr,
-
     pub qtum_addr: Addr,
     pub xqtum_addr: Addr,
 
     pub nft_price: Uint128,
+    // price of mints paid in native coins, none disables them. the amount can be overridden
+    // per sale phase, the denom can't
+    pub native_nft_price: Option<Coin>,
 
+    // default native reward denom for new guilds
     pub usdt_denom: String,
 
-    pub ninja_distribution_schedule: Vec<(u64, u64, Uint128)>,
-    pub scientist_distribution_schedule: Vec<(u64, u64, Uint128)>,
+    // governance parameters, voting period is in seconds
+    pub voting_period: u64,
+    pub quorum: Decimal,
+    pub threshold: Decimal,
+
+    // durations stakes can be locked for
+    pub lock_tiers: Vec<LockTier>,
+
+    // seconds unstaked xqtum stays in the claim queue, 0 returns it right away
+    pub unbonding_period: u64,
+}
+
+// duration is in seconds, e.g. 30 days
+#[cw_serde]
+pub struct LockTier {
+    pub duration: u64,
+    pub reward_multiplier: Decimal,
+    pub voting_multiplier: Decimal,
+}
+
+#[cw_serde]
+pub enum SaleKind {
+    Closed,
+    // merkle_root is the hex encoded root over sha256(address) leaves
+    Allowlist { merkle_root: String },
+    Public,
+}
+
+// none prices fall back to the nft prices of the config, a none cap is unlimited
+#[cw_serde]
+pub struct SalePhase {
+    pub id: u64,
+    pub kind: SaleKind,
+    pub start: u64,
+    pub end: u64,
+    pub price: Option<Uint128>,
+    pub native_price: Option<Uint128>,
+    pub per_address_cap: Option<u32>,
+}
+
+impl SalePhase {
+    pub fn is_active(&self, block_time: u64) -> bool {
+        self.start <= block_time && block_time < self.end
+    }
+}
+
+// how the token id of a mint is picked, assigned ids run from 1 to max_supply
+#[cw_serde]
+pub enum TokenIdMode {
+    // the minter sends the id
+    Chosen,
+    Sequential,
+    // ids are drawn without replacement from the seed and the block data. only the hex
+    // sha256 of the seed is committed, the seed has to be revealed before the first mint
+    Shuffled { seed_hash: String },
+}
+
+#[cw_serde]
+pub struct Collection {
+    pub max_supply: u64,
+    pub minted: u64,
+    pub id_mode: TokenIdMode,
+    // revealed seed of a shuffled collection
+    pub seed: Option<String>,
+}
+
+#[cw_serde]
+pub struct MetadataTrait {
+    pub trait_type: String,
+    pub value: String,
+}
+
+// on-chain metadata every token of the collection is minted with,
+// the name becomes "<name_prefix> #<token id>"
+#[cw_serde]
+pub struct MetadataTemplate {
+    pub name_prefix: Option<String>,
+    pub description: Option<String>,
+    pub attributes: Vec<MetadataTrait>,
+}
+
+#[cw_serde]
+pub struct CollectionMetadata {
+    pub base_token_uri: Option<String>,
+    // returned by the token uri query until the collection is revealed
+    pub placeholder_uri: Option<String>,
+    pub revealed: bool,
+    pub template: Option<MetadataTemplate>,
+}
+
+impl CollectionMetadata {
+    /// Returns the uri a token is minted with. Tokens get their final uri right away, the
+    /// reveal happens off-chain behind it.
+    pub fn token_uri(&self, token_id: &str) -> Option<String> {
+        self.base_token_uri
+            .as_ref()
+            .map(|base_token_uri| format!("{}/{}", base_token_uri, token_id))
+    }
+
+    /// Returns the uri shown for a token, the placeholder until the collection is revealed.
+    pub fn revealed_token_uri(&self, token_id: &str) -> Option<String> {
+        if self.revealed {
+            self.token_uri(token_id)
+        } else {
+            self.placeholder_uri.clone()
+        }
+    }
+}
+
+#[cw_serde]
+pub struct PendingOwner {
+    pub owner: Addr,
+    pub expiry: Expiration,
+}
+
+// circuit breaker switches, a set switch rejects the matching messages
+#[cw_serde]
+#[derive(Default)]
+pub struct PauseState {
+    pub mint: bool,
+    pub lock: bool,
+    pub stake: bool,
+    pub unstake: bool,
+    pub withdraw: bool,
+}
+
+impl PauseState {
+    // both regular exits are closed, the emergency exit opens
+    pub fn exits_paused(&self) -> bool {
+        self.unstake && self.withdraw
+    }
+}
+
+#[cw_serde]
+pub enum Role {
+    // every admin action, implies the other roles
+    Admin,
+    // distribution schedules
+    RewardManager,
+    // minting settings
+    MinterManager,
+    // pause switches
+    Pauser,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::RewardManager => "reward_manager",
+            Role::MinterManager => "minter_manager",
+            Role::Pauser => "pauser",
+        }
+    }
+}
+
+#[cw_serde]
+pub enum AssetInfo {
+    Native { denom: String },
+    Cw20 { contract_addr: Addr },
+}
+
+impl AssetInfo {
+    /// Builds the message paying `amount` of this asset from the contract to `recipient`.
+    pub fn transfer_msg(&self, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+        Ok(match self {
+            AssetInfo::Native { denom } => CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin::new(amount.u128(), denom)],
+            }),
+            AssetInfo::Cw20 { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            }),
+        })
+    }
+}
+
+impl fmt::Display for AssetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetInfo::Native { denom } => write!(f, "{}", denom),
+            AssetInfo::Cw20 { contract_addr } => write!(f, "{}", contract_addr),
+        }
+    }
 }
 
 #[cw_serde]
 pub struct GuildState {
-    pub total_rewards_distributed: Uint128,
     pub total_staker: u64,
     pub total_stake_amount: Uint128,
+    // sum of the stake of every position weighted by its nft multiplier, rewards are shared by it
+    pub total_effective_stake: Uint128,
+    pub total_voting_power: Uint128,
     pub last_distributed: u64,
+    // the first entry is the guild's default reward asset
+    pub rewards: Vec<RewardState>,
+}
+
+#[cw_serde]
+pub struct RewardState {
+    pub asset: AssetInfo,
+    pub total_rewards_distributed: Uint128,
+    pub total_rewards_claimed: Uint128,
     pub global_reward_index: Decimal,
 }
 
+#[cw_serde]
+pub struct Schedule {
+    pub nft_addr: Addr,
+    // gets the unreleased amount back when the schedule is cancelled
+    pub funder: Addr,
+    pub asset: AssetInfo,
+    pub start_date: u64,
+    pub end_date: u64,
+    pub amount: Uint128,
+}
+
+impl Schedule {
+    /// Returns the part of the schedule amount released up to `block_time`.
+    pub fn released_amount(&self, block_time: u64) -> Uint128 {
+        if block_time <= self.start_date {
+            return Uint128::zero();
+        }
+        if block_time >= self.end_date {
+            return self.amount;
+        }
+
+        let distribution_amount_per_second =
+            Decimal::from_ratio(self.amount, self.end_date - self.start_date);
+        distribution_amount_per_second * Uint128::from(block_time - self.start_date)
+    }
+}
+
+pub struct ScheduleIndexes<'a> {
+    pub guild: MultiIndex<'a, Addr, Schedule, u64>,
+}
+
+impl<'a> IndexList<Schedule> for ScheduleIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Schedule>> + '_> {
+        let v: Vec<&dyn Index<Schedule>> = vec![&self.guild];
+        Box::new(v.into_iter())
+    }
+}
+
+// distribution schedules keyed by id and indexed by guild
+pub fn schedules<'a>() -> IndexedMap<'a, u64, Schedule, ScheduleIndexes<'a>> {
+    let indexes = ScheduleIndexes {
+        guild: MultiIndex::new(
+            |_pk, schedule| schedule.nft_addr.clone(),
+            "schedules",
+            "schedules__guild",
+        ),
+    };
+    IndexedMap::new("schedules", indexes)
+}
+
+// totals of a staker over all of its positions
 #[cw_serde]
 pub struct StakerInfo {
     pub stake_amount: Uint128,
+    pub voting_power: Uint128,
+    pub positions: u64,
+    // the part of the voting power given by the locks of the positions
+    pub lock_bonuses: Vec<LockBonus>,
+}
+
+// voting power above the locked amount, it stops counting at the end of the lock
+#[cw_serde]
+pub struct LockBonus {
+    pub end: u64,
+    pub amount: Uint128,
+}
+
+// the weight a guild loses when its locks ending at the same time run out. the reward
+// indices at the end are set once the rewards are distributed up to it
+#[cw_serde]
+#[derive(Default)]
+pub struct LockExpiry {
+    pub effective_stake: Uint128,
+    pub voting_power: Uint128,
+    pub reward_indices: Vec<Decimal>,
+}
+
+#[cw_serde]
+pub struct StakerVotingPower {
+    pub voting_power: Uint128,
+    pub lock_bonuses: Vec<LockBonus>,
+}
+
+// lock ends after last_distributed are still part of the voting power
+#[cw_serde]
+pub struct GuildVotingPower {
+    pub voting_power: Uint128,
+    pub last_distributed: u64,
+}
+
+// a locked nft and the xqtum staked on it
+#[cw_serde]
+pub struct Position {
+    pub owner: Addr,
+    pub nft_addr: Addr,
+    pub token_id: String,
+    pub stake_amount: Uint128,
+    // reward boost of the nft, fixed when it is locked
+    pub multiplier: Decimal,
+    // time locked parts of the stake
+    pub locks: Vec<StakeLock>,
+    pub rewards: Vec<StakerReward>,
+}
+
+impl Position {
+    pub fn locked_amount(&self) -> Uint128 {
+        self.locks.iter().map(|lock| lock.amount).sum()
+    }
+
+    pub fn effective_stake(&self) -> Uint128 {
+        self.effective_stake_at(0)
+    }
+
+    pub fn voting_power(&self) -> Uint128 {
+        self.voting_power_at(0)
+    }
+
+    /// Returns the effective stake once the locks ending by `time` have lost their boost.
+    pub fn effective_stake_at(&self, time: u64) -> Uint128 {
+        let (locked, boosted) = self.running_locks(time, |lock| lock.reward_multiplier);
+        (self.stake_amount - locked + boosted) * self.multiplier
+    }
+
+    /// Returns the voting power once the locks ending by `time` have lost their boost.
+    pub fn voting_power_at(&self, time: u64) -> Uint128 {
+        let (locked, boosted) = self.running_locks(time, |lock| lock.voting_multiplier);
+        self.stake_amount - locked + boosted
+    }
+
+    fn running_locks(
+        &self,
+        time: u64,
+        multiplier: impl Fn(&StakeLock) -> Decimal,
+    ) -> (Uint128, Uint128) {
+        self.locks.iter().filter(|lock| lock.end > time).fold(
+            (Uint128::zero(), Uint128::zero()),
+            |(locked, boosted), lock| {
+                (
+                    locked + lock.amount,
+                    boosted + lock.amount * multiplier(lock),
+                )
+            },
+        )
+    }
+}
+
+// the multipliers of the tier are kept so tier updates don't change running locks
+#[cw_serde]
+pub struct StakeLock {
+    pub amount: Uint128,
+    pub end: u64,
+    pub reward_multiplier: Decimal,
+    pub voting_multiplier: Decimal,
+}
+
+impl StakeLock {
+    pub fn voting_bonus(&self) -> LockBonus {
+        LockBonus {
+            end: self.end,
+            amount: self.amount * self.voting_multiplier - self.amount,
+        }
+    }
+}
+
+pub struct PositionIndexes<'a> {
+    pub owner: MultiIndex<'a, Addr, Position, (Addr, String)>,
+}
+
+impl<'a> IndexList<Position> for PositionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Position>> + '_> {
+        let v: Vec<&dyn Index<Position>> = vec![&self.owner];
+        Box::new(v.into_iter())
+    }
+}
+
+// positions keyed by (nft address, token id) and indexed by owner
+pub fn positions<'a>() -> IndexedMap<'a, (&'a Addr, &'a str), Position, PositionIndexes<'a>> {
+    let indexes = PositionIndexes {
+        owner: MultiIndex::new(
+            |_pk, position| position.owner.clone(),
+            "positions",
+            "positions__owner",
+        ),
+    };
+    IndexedMap::new("positions", indexes)
+}
+
+#[cw_serde]
+pub struct StakerReward {
+    pub asset: AssetInfo,
     pub pending_rewards: Uint128,
     pub reward_index: Decimal,
     pub reward_claimed: Uint128,
-    pub nft_addr: Option<String>,
-    pub token_id: Option<String>,
+}
+
+#[cw_serde]
+pub enum VoteOption {
+    Yes,
+    No,
+    Abstain,
+}
+
+#[cw_serde]
+pub enum ProposalStatus {
+    Open,
+    Passed,
+    Rejected,
+    Executed,
+}
+
+#[cw_serde]
+pub struct Proposal {
+    pub proposer: Addr,
+    pub title: String,
+    pub description: String,
+    pub msgs: Vec<CosmosMsg>,
+    pub status: ProposalStatus,
+    pub start_height: u64,
+    // locks ending by the start don't count for the vote
+    pub start_time: u64,
+    pub expires: u64,
+    // quorum and threshold are copied from config so later updates don't affect running votes
+    pub quorum: Decimal,
+    pub threshold: Decimal,
+    pub total_power: Uint128,
+    pub yes_votes: Uint128,
+    pub no_votes: Uint128,
+    pub abstain_votes: Uint128,
+}
+
+impl Proposal {
+    /// Returns the status of the proposal at `block_time`, resolving open proposals
+    /// whose voting period is over.
+    pub fn current_status(&self, block_time: u64) -> ProposalStatus {
+        if self.status != ProposalStatus::Open {
+            return self.status.clone();
+        }
+
+        if self.is_passed(block_time) {
+            ProposalStatus::Passed
+        } else if block_time >= self.expires {
+            ProposalStatus::Rejected
+        } else {
+            ProposalStatus::Open
+        }
+    }
+
+    fn is_passed(&self, block_time: u64) -> bool {
+        if self.total_power.is_zero() {
+            return false;
+        }
+
+        let total_votes = self.yes_votes + self.no_votes + self.abstain_votes;
+        if total_votes < self.total_power * self.quorum {
+            return false;
+        }
+
+        if block_time >= self.expires {
+            self.yes_votes > (self.yes_votes + self.no_votes) * self.threshold
+        } else {
+            // passes early only if the remaining power can no longer change the result
+            self.yes_votes > self.total_power * self.threshold
+        }
+    }
+}
+
+#[cw_serde]
+pub struct Ballot {
+    pub vote: VoteOption,
+    pub weight: Uint128,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
-pub const NINJA_GUILD: Item<GuildState> = Item::new("ninja_state");
-pub const SCIENTIST_GUILD: Item<GuildState> = Item::new("scientist_state");
+pub const OWNER: Admin = Admin::new("owner");
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+// role grants keyed by (role, address)
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles");
+pub const PAUSE: Item<PauseState> = Item::new("pause");
+// guilds keyed by the address of their nft collection
+pub const GUILDS: Map<&Addr, GuildState> = Map::new("guilds");
+// reward multipliers of a guild, a token entry takes precedence over the trait entries
+pub const TOKEN_MULTIPLIERS: Map<(&Addr, &str), Decimal> = Map::new("token_multipliers");
+// keyed by (nft address, trait type, trait value)
+pub const TRAIT_MULTIPLIERS: Map<(&Addr, &str, &str), Decimal> = Map::new("trait_multipliers");
+pub const SCHEDULE_COUNT: Item<u64> = Item::new("schedule_count");
+// collections the contract mints into, keyed by their nft address
+pub const COLLECTIONS: Map<&Addr, Collection> = Map::new("collections");
+pub const COLLECTION_METADATA: Map<&Addr, CollectionMetadata> = Map::new("collection_metadata");
+// swapped entries of the shuffled id pool, keyed by (nft address, pool index)
+pub const SHUFFLED_IDS: Map<(&Addr, u64), u64> = Map::new("shuffled_ids");
+// sale phases of a collection, minting is closed outside of them
+pub const SALE_PHASES: Map<&Addr, Vec<SalePhase>> = Map::new("sale_phases");
+// mints per (nft address, phase id, minter)
+pub const PHASE_MINTS: Map<(&Addr, u64, &Addr), u32> = Map::new("phase_mints");
 pub const USER_STAKING: Map<&str, StakerInfo> = Map::new("ninja_user_info");
+// unbonding xqtum, the total is kept apart from the stake for the solvency query
+pub const CLAIMS: Claims = Claims::new("claims");
+// where the rewards of a staker are paid to when no recipient is given
+pub const REWARD_RECIPIENTS: Map<&Addr, Addr> = Map::new("reward_recipients");
+// addresses allowed to claim for a staker, keyed by (staker, operator)
+pub const CLAIM_OPERATORS: Map<(&Addr, &Addr), Empty> = Map::new("claim_operators");
+// operators managing the positions of a staker, keyed by (staker, operator)
+pub const STAKING_OPERATORS: Map<(&Addr, &Addr), Expiration> = Map::new("staking_operators");
+// stakers whose xqtum rewards keepers may compound
+pub const AUTO_COMPOUND: Map<&Addr, Empty> = Map::new("auto_compound");
+pub const TOTAL_UNBONDING: Item<Uint128> = Item::new("total_unbonding");
+pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+pub const BALLOTS: Map<(u64, &Addr), Ballot> = Map::new("ballots");
+// keyed by (nft address, lock end)
+pub const LOCK_EXPIRIES: Map<(&Addr, u64), LockExpiry> = Map::new("lock_expiries");
+
+// height indexed copies of the staker stake and the guild totals
+pub const STAKE_SNAPSHOTS: SnapshotMap<&str, Uint128> = SnapshotMap::new(
+    "staker_stake",
+    "staker_stake__checkpoints",
+    "staker_stake__changelog",
+    Strategy::EveryBlock,
+);
+pub const GUILD_STAKE_SNAPSHOTS: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "guild_stake",
+    "guild_stake__checkpoints",
+    "guild_stake__changelog",
+    Strategy::EveryBlock,
+);
+
+// height indexed voting power of the stakers and the guilds, used for governance
+pub const VOTING_POWER_SNAPSHOTS: SnapshotMap<&str, StakerVotingPower> = SnapshotMap::new(
+    "voting_power",
+    "voting_power__checkpoints",
+    "voting_power__changelog",
+    Strategy::EveryBlock,
+);
+pub const GUILD_VOTING_POWER_SNAPSHOTS: SnapshotMap<&Addr, GuildVotingPower> = SnapshotMap::new(
+    "guild_voting_power",
+    "guild_voting_power__checkpoints",
+    "guild_voting_power__changelog",
+    Strategy::EveryBlock,
+);
 
 pub fn user_staking(deps: Deps, sender: &str) -> StdResult<StakerInfo> {
     let staking_info = USER_STAKING.may_load(deps.storage, sender).unwrap();
@@ -52,11 +584,9 @@ pub fn user_staking(deps: Deps, sender: &str) -> StdResult<StakerInfo> {
         Some(staking_info) => Ok(staking_info),
         None => Ok(StakerInfo {
             stake_amount: Uint128::zero(),
-            pending_rewards: Uint128::zero(),
-            reward_claimed: Uint128::zero(),
-            reward_index: Decimal::zero(),
-            nft_addr: None,
-            token_id: None,
+            voting_power: Uint128::zero(),
+            positions: 0,
+            lock_bonuses: vec![],
         }),
     }
 }
@@ -65,42 +595,148 @@ pub fn store_user_staking(
     storage: &mut dyn Storage,
     owner: &str,
     staker_info: &StakerInfo,
+    height: u64,
 ) -> StdResult<Response> {
     USER_STAKING.save(storage, owner, staker_info)?;
+    STAKE_SNAPSHOTS.save(storage, owner, &staker_info.stake_amount, height)?;
+    VOTING_POWER_SNAPSHOTS.save(
+        storage,
+        owner,
+        &StakerVotingPower {
+            voting_power: staker_info.voting_power,
+            lock_bonuses: staker_info.lock_bonuses.clone(),
+        },
+        height,
+    )?;
     Ok(Response::new())
 }
 
-pub fn remove_user_staking(storage: &mut dyn Storage, owner: &str) -> StdResult<Response> {
+pub fn remove_user_staking(
+    storage: &mut dyn Storage,
+    owner: &str,
+    height: u64,
+) -> StdResult<Response> {
     USER_STAKING.remove(storage, owner);
+    STAKE_SNAPSHOTS.remove(storage, owner, height)?;
+    VOTING_POWER_SNAPSHOTS.remove(storage, owner, height)?;
     Ok(Response::new())
 }
 
-pub fn guild_state(deps: Deps, nft_addr: String) -> StdResult<GuildState> {
-    let guild_info: GuildState;
+/// Returns the stake of `owner` at the beginning of block `height`.
+pub fn stake_at_height(storage: &dyn Storage, owner: &str, height: u64) -> StdResult<Uint128> {
+    Ok(STAKE_SNAPSHOTS
+        .may_load_at_height(storage, owner, height)?
+        .unwrap_or_default())
+}
 
-    let config = CONFIG.load(deps.storage)?;
+/// Returns the total stake of the guild at the beginning of block `height`.
+pub fn total_stake_at_height(
+    storage: &dyn Storage,
+    nft_addr: &Addr,
+    height: u64,
+) -> StdResult<Uint128> {
+    Ok(GUILD_STAKE_SNAPSHOTS
+        .may_load_at_height(storage, nft_addr, height)?
+        .unwrap_or_default())
+}
 
-    if nft_addr == config.ninja_nft_addr {
-        guild_info = NINJA_GUILD.load(deps.storage).unwrap();
-    } else {
-        guild_info = SCIENTIST_GUILD.load(deps.storage).unwrap();
+pub fn pause_state(storage: &dyn Storage) -> StdResult<PauseState> {
+    Ok(PAUSE.may_load(storage)?.unwrap_or_default())
+}
+
+/// Returns the voting power of `owner` at the beginning of block `height`, without the
+/// bonus of the locks ending by `time`.
+pub fn voting_power_at_height(
+    storage: &dyn Storage,
+    owner: &str,
+    height: u64,
+    time: u64,
+) -> StdResult<Uint128> {
+    let snapshot = match VOTING_POWER_SNAPSHOTS.may_load_at_height(storage, owner, height)? {
+        Some(snapshot) => snapshot,
+        None => return Ok(Uint128::zero()),
+    };
+
+    let ended: Uint128 = snapshot
+        .lock_bonuses
+        .iter()
+        .filter(|bonus| bonus.end <= time)
+        .map(|bonus| bonus.amount)
+        .sum();
+    Ok(snapshot.voting_power - ended)
+}
+
+/// Returns the total voting power of the guild at the beginning of block `height`, without
+/// the bonus of the locks ending by `time`.
+pub fn total_voting_power_at_height(
+    storage: &dyn Storage,
+    nft_addr: &Addr,
+    height: u64,
+    time: u64,
+) -> StdResult<Uint128> {
+    let snapshot =
+        match GUILD_VOTING_POWER_SNAPSHOTS.may_load_at_height(storage, nft_addr, height)? {
+            Some(snapshot) => snapshot,
+            None => return Ok(Uint128::zero()),
+        };
+    if time <= snapshot.last_distributed {
+        return Ok(snapshot.voting_power);
     }
 
-    return Ok(guild_info);
+    let ended = LOCK_EXPIRIES
+        .prefix(nft_addr)
+        .range(
+            storage,
+            Some(Bound::exclusive(snapshot.last_distributed)),
+            Some(Bound::inclusive(time)),
+            Order::Ascending,
+        )
+        .map(|item| item.map(|(_, expiry)| expiry.voting_power))
+        .sum::<StdResult<Uint128>>()?;
+    // locks added after height can end before a later time
+    Ok(snapshot.voting_power.saturating_sub(ended))
+}
+
+pub fn guild_state(deps: Deps, nft_addr: &Addr) -> StdResult<GuildState> {
+    GUILDS.load(deps.storage, nft_addr)
 }
 
 pub fn store_guild_state(
     storage: &mut dyn Storage,
     guild_state: &GuildState,
-    nft_addr: String,
+    nft_addr: &Addr,
+    height: u64,
 ) -> StdResult<Response> {
-    let config = CONFIG.load(storage)?;
+    GUILDS.save(storage, nft_addr, guild_state)?;
+    GUILD_STAKE_SNAPSHOTS.save(storage, nft_addr, &guild_state.total_stake_amount, height)?;
+    GUILD_VOTING_POWER_SNAPSHOTS.save(
+        storage,
+        nft_addr,
+        &GuildVotingPower {
+            voting_power: guild_state.total_voting_power,
+            last_distributed: guild_state.last_distributed,
+        },
+        height,
+    )?;
 
-    if nft_addr == config.ninja_nft_addr {
-        NINJA_GUILD.save(storage, guild_state).unwrap();
-    } else {
-        SCIENTIST_GUILD.save(storage, guild_state).unwrap();
-    }
+    Ok(Response::new())
+}
+
+pub fn staker_positions(storage: &dyn Storage, owner: &Addr) -> StdResult<Vec<Position>> {
+    positions()
+        .idx
+        .owner
+        .prefix(owner.clone())
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, position)| position))
+        .collect()
+}
 
-    return Ok(Response::new());
+pub fn guild_schedules(storage: &dyn Storage, nft_addr: &Addr) -> StdResult<Vec<(u64, Schedule)>> {
+    schedules()
+        .idx
+        .guild
+        .prefix(nft_addr.clone())
+        .range(storage, None, None, Order::Ascending)
+        .collect()
 }
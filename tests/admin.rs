@@ -0,0 +1,369 @@
+mod common;
+
+use cosmwasm_std::{coins, Addr, Decimal, Timestamp, Uint128};
+use cw_multi_test::Executor;
+use cw_utils::Expiration;
+use qtumdao::msg::{ConfigResponse, ExecuteMsg, OwnershipResponse, QueryMsg, RolesResponse};
+use qtumdao::state::Role;
+use qtumdao::ContractError;
+
+use common::{Suite, ALICE, BOB, OWNER, USDT};
+
+const CAROL: &str = "carol";
+
+fn execute_err(suite: &mut Suite, sender: &str, msg: &ExecuteMsg) -> ContractError {
+    suite
+        .execute(sender, msg, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap()
+}
+
+fn ownership(suite: &Suite) -> OwnershipResponse {
+    suite.query(&QueryMsg::Ownership {})
+}
+
+fn members(suite: &Suite, role: Role) -> Vec<String> {
+    let response: RolesResponse = suite.query(&QueryMsg::Roles {
+        role,
+        start_after: None,
+        limit: None,
+    });
+    response.members
+}
+
+fn propose_new_owner(new_owner: &str, expiry: Option<Expiration>) -> ExecuteMsg {
+    ExecuteMsg::ProposeNewOwner {
+        new_owner: new_owner.to_string(),
+        expiry,
+    }
+}
+
+// any admin message works to check the authorization
+fn set_pause(mint: bool) -> ExecuteMsg {
+    ExecuteMsg::SetPause {
+        mint: Some(mint),
+        lock: None,
+        stake: None,
+        unstake: None,
+        withdraw: None,
+    }
+}
+
+#[test]
+fn ownership_is_transferred_in_two_steps() {
+    let mut suite = Suite::new();
+
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &propose_new_owner(CAROL, None)),
+        ContractError::Unauthorized {}
+    );
+
+    // an expired proposal can't be accepted
+    let expiry = suite.app.block_info().time.seconds() + 10;
+    suite
+        .execute(
+            OWNER,
+            &propose_new_owner(
+                CAROL,
+                Some(Expiration::AtTime(Timestamp::from_seconds(expiry))),
+            ),
+            &[],
+        )
+        .unwrap();
+    suite.advance(10);
+    assert_eq!(
+        execute_err(&mut suite, CAROL, &ExecuteMsg::AcceptOwnership {}),
+        ContractError::OwnershipProposalExpired {}
+    );
+
+    // a cancelled proposal is gone
+    suite
+        .execute(OWNER, &ExecuteMsg::CancelOwnershipProposal {}, &[])
+        .unwrap();
+    assert_eq!(
+        execute_err(&mut suite, CAROL, &ExecuteMsg::AcceptOwnership {}),
+        ContractError::NoPendingOwner {}
+    );
+
+    suite
+        .execute(OWNER, &propose_new_owner(CAROL, None), &[])
+        .unwrap();
+    assert_eq!(ownership(&suite).pending_owner, Some(CAROL.to_string()));
+    assert_eq!(
+        execute_err(&mut suite, BOB, &ExecuteMsg::AcceptOwnership {}),
+        ContractError::Unauthorized {}
+    );
+    suite
+        .execute(CAROL, &ExecuteMsg::AcceptOwnership {}, &[])
+        .unwrap();
+    let ownership = ownership(&suite);
+    assert_eq!(ownership.owner, Some(CAROL.to_string()));
+    assert_eq!(ownership.pending_owner, None);
+
+    assert_eq!(
+        execute_err(&mut suite, OWNER, &set_pause(true)),
+        ContractError::Unauthorized {}
+    );
+    suite.execute(CAROL, &set_pause(true), &[]).unwrap();
+}
+
+#[test]
+fn renouncing_ownership_revokes_the_admins() {
+    let mut suite = Suite::new();
+    for (role, address) in [(Role::Admin, ALICE), (Role::Pauser, BOB)] {
+        suite
+            .execute(
+                OWNER,
+                &ExecuteMsg::GrantRole {
+                    role,
+                    address: address.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+    suite
+        .execute(OWNER, &propose_new_owner(CAROL, None), &[])
+        .unwrap();
+
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &ExecuteMsg::RenounceOwnership {}),
+        ContractError::Unauthorized {}
+    );
+    suite
+        .execute(OWNER, &ExecuteMsg::RenounceOwnership {}, &[])
+        .unwrap();
+    assert_eq!(
+        ownership(&suite),
+        OwnershipResponse {
+            owner: None,
+            pending_owner: None,
+            pending_expiry: None,
+        }
+    );
+    assert!(members(&suite, Role::Admin).is_empty());
+
+    for sender in [OWNER, ALICE] {
+        assert_eq!(
+            execute_err(&mut suite, sender, &set_pause(true)),
+            ContractError::Unauthorized {}
+        );
+    }
+    assert_eq!(
+        execute_err(&mut suite, CAROL, &ExecuteMsg::AcceptOwnership {}),
+        ContractError::NoPendingOwner {}
+    );
+
+    // the other grants keep working
+    suite.execute(BOB, &set_pause(true), &[]).unwrap();
+}
+
+// only the given fields are set
+fn update_config(
+    xqtum_addr: Option<&Addr>,
+    nft_price: Option<u128>,
+    quorum: Option<Decimal>,
+    unbonding_period: Option<u64>,
+) -> ExecuteMsg {
+    ExecuteMsg::UpdateConfig {
+        qtum_addr: None,
+        xqtum_addr: xqtum_addr.map(|addr| addr.to_string()),
+        nft_price: nft_price.map(Uint128::new),
+        native_nft_price: None,
+        disable_native_mint: None,
+        usdt_denom: None,
+        voting_period: None,
+        quorum,
+        threshold: None,
+        lock_tiers: None,
+        unbonding_period,
+    }
+}
+
+#[test]
+fn config_fields_are_updated_one_by_one() {
+    let mut suite = Suite::new();
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::GrantRole {
+                role: Role::MinterManager,
+                address: BOB.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    let nft_price = update_config(None, Some(20), None, None);
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &nft_price),
+        ContractError::Unauthorized {}
+    );
+    assert_eq!(
+        execute_err(&mut suite, BOB, &update_config(None, Some(0), None, None)),
+        ContractError::ZeroNftPrice {}
+    );
+    suite.execute(BOB, &nft_price, &[]).unwrap();
+
+    // governance params belong to the admins
+    let quorum = update_config(None, None, Some(Decimal::percent(40)), None);
+    assert_eq!(
+        execute_err(&mut suite, BOB, &quorum),
+        ContractError::Unauthorized {}
+    );
+    assert_eq!(
+        execute_err(
+            &mut suite,
+            OWNER,
+            &update_config(None, None, Some(Decimal::percent(101)), None)
+        ),
+        ContractError::InvalidThreshold {}
+    );
+    suite.execute(OWNER, &quorum, &[]).unwrap();
+
+    let config: ConfigResponse = suite.query(&QueryMsg::Config {});
+    assert_eq!(config.nft_price, Uint128::new(20));
+    assert_eq!(config.quorum, Decimal::percent(40));
+    assert_eq!(config.threshold, Decimal::percent(50));
+    assert_eq!(config.xqtum_addr, suite.xqtum.to_string());
+}
+
+#[test]
+fn xqtum_is_kept_while_staked_or_unbonding() {
+    let mut suite = Suite::new();
+    let (ninja, qtum) = (suite.ninja.clone(), suite.qtum.clone());
+    suite
+        .execute(OWNER, &update_config(None, None, None, Some(50)), &[])
+        .unwrap();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+
+    let swap = update_config(Some(&qtum), None, None, None);
+    assert_eq!(
+        execute_err(&mut suite, OWNER, &swap),
+        ContractError::StakeOutstanding {}
+    );
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::Unlock {
+                nft_addr: ninja.to_string(),
+                token_id: "1".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        execute_err(&mut suite, OWNER, &swap),
+        ContractError::StakeOutstanding {}
+    );
+
+    // setting the same token stays possible
+    let xqtum = suite.xqtum.clone();
+    suite
+        .execute(OWNER, &update_config(Some(&xqtum), None, None, None), &[])
+        .unwrap();
+
+    suite.advance(50);
+    suite
+        .execute(ALICE, &ExecuteMsg::ClaimUnbonded {}, &[])
+        .unwrap();
+    suite.execute(OWNER, &swap, &[]).unwrap();
+    let config: ConfigResponse = suite.query(&QueryMsg::Config {});
+    assert_eq!(config.xqtum_addr, qtum.to_string());
+}
+
+fn grant_role(role: Role, address: &str) -> ExecuteMsg {
+    ExecuteMsg::GrantRole {
+        role,
+        address: address.to_string(),
+    }
+}
+
+fn revoke_role(role: Role, address: &str) -> ExecuteMsg {
+    ExecuteMsg::RevokeRole {
+        role,
+        address: address.to_string(),
+    }
+}
+
+#[test]
+fn roles_are_managed_by_the_admins() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &grant_role(Role::Pauser, BOB)),
+        ContractError::Unauthorized {}
+    );
+    suite
+        .execute(OWNER, &grant_role(Role::Admin, ALICE), &[])
+        .unwrap();
+
+    // the admin role itself stays with the owner
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &grant_role(Role::Admin, BOB)),
+        ContractError::Unauthorized {}
+    );
+    suite
+        .execute(ALICE, &grant_role(Role::RewardManager, BOB), &[])
+        .unwrap();
+    assert_eq!(members(&suite, Role::RewardManager), vec![BOB.to_string()]);
+
+    // the treasury bot funds schedules without holding the owner key
+    let start_date = suite.app.block_info().time.seconds();
+    suite
+        .app
+        .send_tokens(
+            Addr::unchecked(OWNER),
+            Addr::unchecked(BOB),
+            &coins(100, USDT),
+        )
+        .unwrap();
+    suite
+        .execute(
+            BOB,
+            &ExecuteMsg::SetDistribution {
+                nft_addr: ninja.to_string(),
+                asset: None,
+                start_date,
+                end_date: start_date + 10,
+                amount: Uint128::new(100),
+            },
+            &coins(100, USDT),
+        )
+        .unwrap();
+    assert_eq!(
+        execute_err(&mut suite, BOB, &set_pause(true)),
+        ContractError::Unauthorized {}
+    );
+
+    suite
+        .execute(ALICE, &revoke_role(Role::RewardManager, BOB), &[])
+        .unwrap();
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &revoke_role(Role::RewardManager, BOB)),
+        ContractError::RoleNotGranted {}
+    );
+    assert_eq!(
+        execute_err(
+            &mut suite,
+            BOB,
+            &ExecuteMsg::CancelSchedule { schedule_id: 1 }
+        ),
+        ContractError::Unauthorized {}
+    );
+
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &revoke_role(Role::Admin, ALICE)),
+        ContractError::Unauthorized {}
+    );
+    suite
+        .execute(OWNER, &revoke_role(Role::Admin, ALICE), &[])
+        .unwrap();
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &set_pause(true)),
+        ContractError::Unauthorized {}
+    );
+}
@@ -0,0 +1,358 @@
+#![allow(dead_code)]
+
+use anyhow::Result as AnyResult;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_schema::serde::de::DeserializeOwned;
+use cosmwasm_std::{coins, to_json_binary, Addr, Coin, Decimal, Empty, Uint128};
+use cw20::{Cw20Coin, Cw20ExecuteMsg};
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+
+use qtumdao::msg::{Cw20HookMsg, Cw721HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg};
+use qtumdao::state::{AssetInfo, LockTier, MetadataTrait};
+
+pub const OWNER: &str = "owner";
+pub const ALICE: &str = "alice";
+pub const BOB: &str = "bob";
+pub const USDT: &str = "uusdt";
+pub const NATIVE_QTUM: &str = "uqtum";
+pub const LOCK_DURATION: u64 = 100;
+
+pub fn dao_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            qtumdao::contract::execute,
+            qtumdao::contract::instantiate,
+            qtumdao::contract::query,
+        )
+        .with_migrate(qtumdao::contract::migrate),
+    )
+}
+
+fn cw20_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    ))
+}
+
+// cw721 with an on-chain metadata extension, tokens without metadata are minted with none
+#[cw_serde]
+#[derive(Default)]
+pub struct NftExtension {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub attributes: Option<Vec<MetadataTrait>>,
+}
+
+type NftContract<'a> = cw721_base::Cw721Contract<'a, Option<NftExtension>, Empty, Empty, Empty>;
+
+fn cw721_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        |deps, env, info, msg: cw721_base::ExecuteMsg<Option<NftExtension>, Empty>| {
+            NftContract::default().execute(deps, env, info, msg)
+        },
+        |deps, env, info, msg: cw721_base::InstantiateMsg| {
+            NftContract::default().instantiate(deps, env, info, msg)
+        },
+        |deps, env, msg: cw721_base::QueryMsg<Empty>| NftContract::default().query(deps, env, msg),
+    ))
+}
+
+// the dao with both tokens, a ninja and a scientist guild and minted nfts:
+// ninja 1 and 2 and scientist 7 belong to alice, ninja 3 to bob. alice holds native qtum too
+pub struct Suite {
+    pub app: App,
+    pub dao: Addr,
+    pub qtum: Addr,
+    pub xqtum: Addr,
+    pub ninja: Addr,
+    pub scientist: Addr,
+    pub cw20_id: u64,
+    pub cw721_id: u64,
+}
+
+impl Suite {
+    pub fn new() -> Self {
+        let owner = Addr::unchecked(OWNER);
+        let mut app = App::new(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &owner, coins(1_000_000, USDT))
+                .unwrap();
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(ALICE), coins(1000, NATIVE_QTUM))
+                .unwrap();
+        });
+
+        let cw20_id = app.store_code(cw20_contract());
+        let cw721_id = app.store_code(cw721_contract());
+        let dao_id = app.store_code(dao_contract());
+
+        let xqtum = instantiate_token(&mut app, cw20_id, "xqtum", &[ALICE, BOB]);
+        let qtum = instantiate_token(&mut app, cw20_id, "qtum", &[ALICE]);
+
+        let dao = app
+            .instantiate_contract(
+                dao_id,
+                owner.clone(),
+                &InstantiateMsg {
+                    qtum_addr: qtum.to_string(),
+                    xqtum_addr: xqtum.to_string(),
+                    usdt_denom: USDT.to_string(),
+                    nft_price: Uint128::new(10),
+                    native_nft_price: None,
+                    voting_period: 100,
+                    quorum: Decimal::percent(30),
+                    threshold: Decimal::percent(50),
+                    lock_tiers: vec![LockTier {
+                        duration: LOCK_DURATION,
+                        reward_multiplier: Decimal::percent(200),
+                        voting_multiplier: Decimal::percent(300),
+                    }],
+                    unbonding_period: 0,
+                },
+                &[],
+                "qtumdao",
+                None,
+            )
+            .unwrap();
+
+        let mut suite = Suite {
+            app,
+            dao,
+            qtum,
+            xqtum,
+            ninja: Addr::unchecked(""),
+            scientist: Addr::unchecked(""),
+            cw20_id,
+            cw721_id,
+        };
+        suite.ninja = suite.create_nft("ninja", OWNER, &[("1", ALICE), ("2", ALICE), ("3", BOB)]);
+        suite.add_guild(&suite.ninja.clone(), None).unwrap();
+        suite.scientist = suite.create_nft("scientist", OWNER, &[("7", ALICE)]);
+        suite.add_guild(&suite.scientist.clone(), None).unwrap();
+        suite
+    }
+
+    // a new collection minted by the given minter, the tokens are minted by the owner
+    // if it is the minter
+    pub fn create_nft(&mut self, name: &str, minter: &str, tokens: &[(&str, &str)]) -> Addr {
+        let nft_addr = self
+            .app
+            .instantiate_contract(
+                self.cw721_id,
+                Addr::unchecked(OWNER),
+                &cw721_base::InstantiateMsg {
+                    name: name.to_string(),
+                    symbol: name.to_uppercase(),
+                    minter: minter.to_string(),
+                },
+                &[],
+                name,
+                None,
+            )
+            .unwrap();
+        for (token_id, token_owner) in tokens {
+            self.mint_nft(&nft_addr, token_id, token_owner, None);
+        }
+        nft_addr
+    }
+
+    // minted by the owner
+    pub fn mint_nft(
+        &mut self,
+        nft_addr: &Addr,
+        token_id: &str,
+        owner: &str,
+        extension: Option<NftExtension>,
+    ) {
+        let mint: cw721_base::ExecuteMsg<Option<NftExtension>, Empty> =
+            cw721_base::ExecuteMsg::Mint(cw721_base::MintMsg {
+                token_id: token_id.to_string(),
+                owner: owner.to_string(),
+                token_uri: None,
+                extension,
+            });
+        self.app
+            .execute_contract(Addr::unchecked(OWNER), nft_addr.clone(), &mint, &[])
+            .unwrap();
+    }
+
+    pub fn add_guild(
+        &mut self,
+        nft_addr: &Addr,
+        reward_asset: Option<AssetInfo>,
+    ) -> AnyResult<AppResponse> {
+        self.execute(
+            OWNER,
+            &ExecuteMsg::AddGuild {
+                nft_addr: nft_addr.to_string(),
+                reward_asset,
+            },
+            &[],
+        )
+    }
+
+    pub fn execute(
+        &mut self,
+        sender: &str,
+        msg: &ExecuteMsg,
+        funds: &[Coin],
+    ) -> AnyResult<AppResponse> {
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.dao.clone(), msg, funds)
+    }
+
+    pub fn query<T: DeserializeOwned>(&self, msg: &QueryMsg) -> T {
+        self.app.wrap().query_wasm_smart(&self.dao, msg).unwrap()
+    }
+
+    pub fn lock(&mut self, owner: &str, nft_addr: &Addr, token_id: &str) -> AnyResult<AppResponse> {
+        let msg: cw721_base::ExecuteMsg<Empty, Empty> = cw721_base::ExecuteMsg::SendNft {
+            contract: self.dao.to_string(),
+            token_id: token_id.to_string(),
+            msg: to_json_binary(&Cw721HookMsg::Lock {})?,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(owner), nft_addr.clone(), &msg, &[])
+    }
+
+    pub fn stake(
+        &mut self,
+        owner: &str,
+        nft_addr: &Addr,
+        token_id: &str,
+        amount: u128,
+        lock_duration: Option<u64>,
+    ) -> AnyResult<AppResponse> {
+        let msg = Cw20ExecuteMsg::Send {
+            contract: self.dao.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(&Cw20HookMsg::Stake {
+                nft_addr: nft_addr.to_string(),
+                token_id: token_id.to_string(),
+                lock_duration,
+            })?,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(owner), self.xqtum.clone(), &msg, &[])
+    }
+
+    // paid in qtum through the cw20 hook
+    pub fn mint(
+        &mut self,
+        minter: &str,
+        nft_addr: &Addr,
+        id: Option<&str>,
+        proof: Option<Vec<String>>,
+        amount: u128,
+    ) -> AnyResult<AppResponse> {
+        let msg = Cw20ExecuteMsg::Send {
+            contract: self.dao.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(&Cw20HookMsg::Mint {
+                id: id.map(|id| id.to_string()),
+                nft_addr: nft_addr.to_string(),
+                proof,
+            })?,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(minter), self.qtum.clone(), &msg, &[])
+    }
+
+    // usdt rewards released evenly from now on
+    pub fn distribute(&mut self, nft_addr: &Addr, duration: u64, amount: u128) {
+        let start_date = self.app.block_info().time.seconds();
+        self.execute(
+            OWNER,
+            &ExecuteMsg::SetDistribution {
+                nft_addr: nft_addr.to_string(),
+                asset: None,
+                start_date,
+                end_date: start_date + duration,
+                amount: Uint128::new(amount),
+            },
+            &coins(amount, USDT),
+        )
+        .unwrap();
+    }
+
+    // cw20 rewards released evenly from now on, sent from the token balance of the sender
+    pub fn fund(
+        &mut self,
+        sender: &str,
+        token: &Addr,
+        nft_addr: &Addr,
+        duration: u64,
+        amount: u128,
+    ) -> AnyResult<AppResponse> {
+        let start_date = self.app.block_info().time.seconds();
+        let msg = Cw20ExecuteMsg::Send {
+            contract: self.dao.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(&Cw20HookMsg::FundSchedule {
+                nft_addr: nft_addr.to_string(),
+                start_date,
+                end_date: start_date + duration,
+            })?,
+        };
+        self.app
+            .execute_contract(Addr::unchecked(sender), token.clone(), &msg, &[])
+    }
+
+    pub fn advance(&mut self, seconds: u64) {
+        self.app.update_block(|block| {
+            block.height += 1;
+            block.time = block.time.plus_seconds(seconds);
+        });
+    }
+
+    pub fn usdt_balance(&self, address: &str) -> Uint128 {
+        self.app.wrap().query_balance(address, USDT).unwrap().amount
+    }
+
+    pub fn xqtum_balance(&self, address: &str) -> Uint128 {
+        self.token_balance(&self.xqtum, address)
+    }
+
+    pub fn token_balance(&self, token: &Addr, address: &str) -> Uint128 {
+        let response: cw20::BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                token,
+                &cw20::Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        response.balance
+    }
+}
+
+fn instantiate_token(app: &mut App, code_id: u64, name: &str, holders: &[&str]) -> Addr {
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(OWNER),
+        &cw20_base::msg::InstantiateMsg {
+            name: name.to_string(),
+            symbol: name.to_uppercase(),
+            decimals: 6,
+            initial_balances: holders
+                .iter()
+                .map(|holder| Cw20Coin {
+                    address: holder.to_string(),
+                    amount: Uint128::new(1000),
+                })
+                .collect(),
+            mint: None,
+            marketing: None,
+        },
+        &[],
+        name,
+        None,
+    )
+    .unwrap()
+}
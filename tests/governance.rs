@@ -0,0 +1,285 @@
+mod common;
+
+use cosmwasm_std::{coins, Addr, BankMsg, CosmosMsg, Uint128};
+use cw_multi_test::Executor;
+use qtumdao::msg::{
+    ExecuteMsg, ProposalResponse, ProposalsResponse, QueryMsg, StakeAtHeightResponse,
+    TotalStakeAtHeightResponse, VoteResponse,
+};
+use qtumdao::state::{ProposalStatus, VoteOption};
+use qtumdao::ContractError;
+
+use common::{Suite, ALICE, BOB, OWNER, USDT};
+
+const CAROL: &str = "carol";
+const VOTING_PERIOD: u64 = 100;
+
+fn proposal(suite: &Suite, proposal_id: u64) -> ProposalResponse {
+    suite.query(&QueryMsg::Proposal { proposal_id })
+}
+
+fn vote(proposal_id: u64, vote: VoteOption) -> ExecuteMsg {
+    ExecuteMsg::Vote { proposal_id, vote }
+}
+
+// alice and bob stake on their ninjas, the voting power counts from the next block
+fn staked_suite(alice: u128, bob: u128) -> Suite {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.lock(BOB, &ninja, "3").unwrap();
+    suite.stake(ALICE, &ninja, "1", alice, None).unwrap();
+    suite.stake(BOB, &ninja, "3", bob, None).unwrap();
+    suite.advance(1);
+    suite
+}
+
+fn propose(suite: &mut Suite, proposer: &str, msgs: Vec<CosmosMsg>) -> u64 {
+    suite
+        .execute(
+            proposer,
+            &ExecuteMsg::Propose {
+                title: "title".to_string(),
+                description: "description".to_string(),
+                msgs,
+            },
+            &[],
+        )
+        .unwrap();
+    let proposals: ProposalsResponse = suite.query(&QueryMsg::Proposals {
+        start_after: None,
+        limit: None,
+    });
+    proposals.proposals.last().unwrap().id
+}
+
+#[test]
+fn proposal_passes_early_and_executes() {
+    let mut suite = staked_suite(100, 50);
+    let dao = suite.dao.clone();
+    suite
+        .app
+        .send_tokens(Addr::unchecked(OWNER), dao, &coins(40, USDT))
+        .unwrap();
+
+    let err = suite
+        .execute(
+            CAROL,
+            &ExecuteMsg::Propose {
+                title: "title".to_string(),
+                description: "description".to_string(),
+                msgs: vec![],
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NoVotingPower {}
+    );
+
+    let payout = CosmosMsg::Bank(BankMsg::Send {
+        to_address: CAROL.to_string(),
+        amount: coins(40, USDT),
+    });
+    let proposal_id = propose(&mut suite, BOB, vec![payout]);
+    assert_eq!(proposal(&suite, proposal_id).total_power, Uint128::new(150));
+
+    // nothing is dispatched before the proposal passed
+    let err = suite
+        .execute(ALICE, &ExecuteMsg::Execute { proposal_id }, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::WrongExecuteStatus {}
+    );
+
+    suite
+        .execute(BOB, &vote(proposal_id, VoteOption::No), &[])
+        .unwrap();
+    let err = suite
+        .execute(BOB, &vote(proposal_id, VoteOption::Yes), &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::AlreadyVoted {}
+    );
+    assert_eq!(proposal(&suite, proposal_id).status, ProposalStatus::Open);
+
+    // 100 of 150 yes can no longer be outvoted
+    suite
+        .execute(ALICE, &vote(proposal_id, VoteOption::Yes), &[])
+        .unwrap();
+    assert_eq!(proposal(&suite, proposal_id).status, ProposalStatus::Passed);
+    let ballot: VoteResponse = suite.query(&QueryMsg::Vote {
+        proposal_id,
+        voter: ALICE.to_string(),
+    });
+    assert_eq!(ballot.vote, Some(VoteOption::Yes));
+    assert_eq!(ballot.weight, Uint128::new(100));
+
+    suite
+        .execute(CAROL, &ExecuteMsg::Execute { proposal_id }, &[])
+        .unwrap();
+    assert_eq!(suite.usdt_balance(CAROL), Uint128::new(40));
+    assert_eq!(
+        proposal(&suite, proposal_id).status,
+        ProposalStatus::Executed
+    );
+    suite
+        .execute(CAROL, &ExecuteMsg::Execute { proposal_id }, &[])
+        .unwrap_err();
+}
+
+#[test]
+fn proposal_passes_by_the_cast_votes_after_expiry() {
+    let mut suite = staked_suite(100, 100);
+    let proposal_id = propose(&mut suite, ALICE, vec![]);
+
+    // half of the total power is not enough to pass early
+    suite
+        .execute(ALICE, &vote(proposal_id, VoteOption::Yes), &[])
+        .unwrap();
+    assert_eq!(proposal(&suite, proposal_id).status, ProposalStatus::Open);
+
+    suite.advance(VOTING_PERIOD);
+    assert_eq!(proposal(&suite, proposal_id).status, ProposalStatus::Passed);
+    let err = suite
+        .execute(BOB, &vote(proposal_id, VoteOption::No), &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ProposalNotOpen {}
+    );
+    suite
+        .execute(BOB, &ExecuteMsg::Execute { proposal_id }, &[])
+        .unwrap();
+}
+
+#[test]
+fn rejected_proposal_is_closed() {
+    let mut suite = staked_suite(100, 100);
+    let proposal_id = propose(&mut suite, ALICE, vec![]);
+
+    suite
+        .execute(ALICE, &vote(proposal_id, VoteOption::Yes), &[])
+        .unwrap();
+    suite
+        .execute(BOB, &vote(proposal_id, VoteOption::No), &[])
+        .unwrap();
+
+    // a tie is decided at the end of the voting period
+    let err = suite
+        .execute(BOB, &ExecuteMsg::Close { proposal_id }, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::WrongCloseStatus {}
+    );
+
+    suite.advance(VOTING_PERIOD);
+    suite
+        .execute(ALICE, &ExecuteMsg::Execute { proposal_id }, &[])
+        .unwrap_err();
+    suite
+        .execute(BOB, &ExecuteMsg::Close { proposal_id }, &[])
+        .unwrap();
+    assert_eq!(
+        proposal(&suite, proposal_id).status,
+        ProposalStatus::Rejected
+    );
+    suite
+        .execute(BOB, &ExecuteMsg::Close { proposal_id }, &[])
+        .unwrap_err();
+}
+
+#[test]
+fn proposal_without_quorum_is_rejected() {
+    let mut suite = staked_suite(20, 100);
+    let proposal_id = propose(&mut suite, ALICE, vec![]);
+
+    // 20 of 120 is below the 30% quorum
+    suite
+        .execute(ALICE, &vote(proposal_id, VoteOption::Yes), &[])
+        .unwrap();
+    suite.advance(VOTING_PERIOD);
+    assert_eq!(
+        proposal(&suite, proposal_id).status,
+        ProposalStatus::Rejected
+    );
+}
+
+#[test]
+fn votes_are_weighted_by_the_stake_at_the_proposal_start() {
+    let mut suite = staked_suite(100, 100);
+    let ninja = suite.ninja.clone();
+    let start_height = suite.app.block_info().height;
+    let proposal_id = propose(&mut suite, ALICE, vec![]);
+
+    // stake moved after the start doesn't change the weights
+    suite.stake(ALICE, &ninja, "1", 200, None).unwrap();
+    suite
+        .execute(
+            BOB,
+            &ExecuteMsg::Unstake {
+                nft_addr: ninja.to_string(),
+                token_id: "3".to_string(),
+                amount: Uint128::new(100),
+            },
+            &[],
+        )
+        .unwrap();
+    suite.advance(1);
+    suite
+        .execute(ALICE, &vote(proposal_id, VoteOption::Yes), &[])
+        .unwrap();
+    suite
+        .execute(BOB, &vote(proposal_id, VoteOption::No), &[])
+        .unwrap();
+    let proposal = proposal(&suite, proposal_id);
+    assert_eq!(proposal.yes_votes, Uint128::new(100));
+    assert_eq!(proposal.no_votes, Uint128::new(100));
+
+    let stake: StakeAtHeightResponse = suite.query(&QueryMsg::StakeAtHeight {
+        staker: ALICE.to_string(),
+        height: start_height,
+    });
+    assert_eq!(stake.stake_amount, Uint128::new(100));
+    let stake: StakeAtHeightResponse = suite.query(&QueryMsg::StakeAtHeight {
+        staker: ALICE.to_string(),
+        height: start_height + 1,
+    });
+    assert_eq!(stake.stake_amount, Uint128::new(300));
+    let total: TotalStakeAtHeightResponse = suite.query(&QueryMsg::TotalStakeAtHeight {
+        guild: ninja.to_string(),
+        height: start_height,
+    });
+    assert_eq!(total.total_stake_amount, Uint128::new(200));
+    let total: TotalStakeAtHeightResponse = suite.query(&QueryMsg::TotalStakeAtHeight {
+        guild: ninja.to_string(),
+        height: start_height + 1,
+    });
+    assert_eq!(total.total_stake_amount, Uint128::new(300));
+}
+
+#[test]
+fn stake_of_the_proposal_block_has_no_weight() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.lock(BOB, &ninja, "3").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite.advance(1);
+    let proposal_id = propose(&mut suite, ALICE, vec![]);
+
+    // bob only stakes in the block of the proposal
+    suite.stake(BOB, &ninja, "3", 100, None).unwrap();
+    let err = suite
+        .execute(BOB, &vote(proposal_id, VoteOption::No), &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NoVotingPower {}
+    );
+    assert_eq!(proposal(&suite, proposal_id).total_power, Uint128::new(100));
+}
@@ -0,0 +1,232 @@
+mod common;
+
+use cosmwasm_std::{coins, Addr, Uint128};
+use cw20::Cw20ExecuteMsg;
+use cw_multi_test::Executor;
+use qtumdao::msg::{ExecuteMsg, GuildsResponse, QueryMsg, StateResponse};
+use qtumdao::state::AssetInfo;
+use qtumdao::ContractError;
+
+use common::{Suite, ALICE, BOB, OWNER, USDT};
+
+fn guild_addrs(suite: &Suite) -> Vec<String> {
+    let guilds: GuildsResponse = suite.query(&QueryMsg::Guilds {
+        start_after: None,
+        limit: None,
+        block_time: None,
+    });
+    guilds.guilds.into_iter().map(|g| g.nft_addr).collect()
+}
+
+fn remove_guild(suite: &mut Suite, nft_addr: &Addr) -> Result<(), ContractError> {
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::RemoveGuild {
+                nft_addr: nft_addr.to_string(),
+            },
+            &[],
+        )
+        .map(|_| ())
+        .map_err(|err| err.downcast().unwrap())
+}
+
+#[test]
+fn third_guild_is_added_without_redeploying() {
+    let mut suite = Suite::new();
+    let samurai = suite.create_nft("samurai", OWNER, &[("9", BOB)]);
+
+    // unregistered collections can't be locked
+    suite.lock(BOB, &samurai, "9").unwrap_err();
+
+    let err = suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::AddGuild {
+                nft_addr: samurai.to_string(),
+                reward_asset: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+    suite.add_guild(&samurai, None).unwrap();
+    let err = suite.add_guild(&samurai, None).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::GuildAlreadyExists {}
+    );
+    assert_eq!(guild_addrs(&suite).len(), 3);
+
+    // the new guild has its own pool and schedules
+    suite.lock(BOB, &samurai, "9").unwrap();
+    suite.stake(BOB, &samurai, "9", 100, None).unwrap();
+    suite.distribute(&samurai, 10, 50);
+    suite.advance(10);
+    suite
+        .execute(BOB, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    assert_eq!(suite.usdt_balance(BOB), Uint128::new(50));
+}
+
+#[test]
+fn guild_is_removed_once_empty() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    assert_eq!(
+        remove_guild(&mut suite, &ninja),
+        Err(ContractError::GuildNotEmpty {})
+    );
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::Unlock {
+                nft_addr: ninja.to_string(),
+                token_id: "1".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    // a schedule that hasn't started yet has to be cancelled first
+    let start_date = suite.app.block_info().time.seconds() + 10;
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::SetDistribution {
+                nft_addr: ninja.to_string(),
+                asset: None,
+                start_date,
+                end_date: start_date + 10,
+                amount: Uint128::new(100),
+            },
+            &coins(100, USDT),
+        )
+        .unwrap();
+    assert_eq!(
+        remove_guild(&mut suite, &ninja),
+        Err(ContractError::GuildHasSchedules {})
+    );
+    suite
+        .execute(OWNER, &ExecuteMsg::CancelSchedule { schedule_id: 1 }, &[])
+        .unwrap();
+
+    remove_guild(&mut suite, &ninja).unwrap();
+    assert_eq!(guild_addrs(&suite), vec![suite.scientist.to_string()]);
+    suite.lock(ALICE, &ninja, "1").unwrap_err();
+    assert_eq!(
+        remove_guild(&mut suite, &ninja),
+        Err(ContractError::UnsupportedNft {})
+    );
+}
+
+#[test]
+fn rewards_are_paid_in_the_reward_asset_of_the_guild() {
+    let mut suite = Suite::new();
+    let qtum = suite.qtum.clone();
+    let samurai = suite.create_nft("samurai", OWNER, &[("9", BOB)]);
+    suite
+        .add_guild(
+            &samurai,
+            Some(AssetInfo::Cw20 {
+                contract_addr: qtum.clone(),
+            }),
+        )
+        .unwrap();
+    let scientist = suite.scientist.clone();
+
+    suite.lock(BOB, &samurai, "9").unwrap();
+    suite.stake(BOB, &samurai, "9", 100, None).unwrap();
+    suite.lock(ALICE, &scientist, "7").unwrap();
+    suite.stake(ALICE, &scientist, "7", 100, None).unwrap();
+
+    // cw20 rewards can only be funded through the token
+    let start_date = suite.app.block_info().time.seconds();
+    let err = suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::SetDistribution {
+                nft_addr: samurai.to_string(),
+                asset: None,
+                start_date,
+                end_date: start_date + 10,
+                amount: Uint128::new(100),
+            },
+            &coins(100, USDT),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::UnsupportedToken {}
+    );
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            qtum.clone(),
+            &Cw20ExecuteMsg::Transfer {
+                recipient: OWNER.to_string(),
+                amount: Uint128::new(100),
+            },
+            &[],
+        )
+        .unwrap();
+    suite.fund(ALICE, &qtum, &samurai, 10, 50).unwrap_err();
+    suite.fund(OWNER, &qtum, &samurai, 10, 100).unwrap();
+    suite.distribute(&scientist, 10, 40);
+    suite.advance(10);
+
+    suite
+        .execute(BOB, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    assert_eq!(suite.token_balance(&qtum, BOB), Uint128::new(100));
+    assert_eq!(suite.usdt_balance(BOB), Uint128::zero());
+    suite
+        .execute(ALICE, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    assert_eq!(suite.usdt_balance(ALICE), Uint128::new(40));
+    assert_eq!(suite.token_balance(&qtum, ALICE), Uint128::new(900));
+}
+
+#[test]
+fn guild_reward_asset_is_validated() {
+    let mut suite = Suite::new();
+    let monk = suite.create_nft("monk", OWNER, &[]);
+    let err = suite
+        .add_guild(
+            &monk,
+            Some(AssetInfo::Native {
+                denom: "u".to_string(),
+            }),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidDenom {
+            denom: "u".to_string()
+        }
+    );
+    suite
+        .add_guild(
+            &monk,
+            Some(AssetInfo::Native {
+                denom: "uatom".to_string(),
+            }),
+        )
+        .unwrap();
+    let state: StateResponse = suite.query(&QueryMsg::State {
+        nft_addr: monk.to_string(),
+        block_time: None,
+    });
+    assert_eq!(
+        state.rewards[0].asset,
+        AssetInfo::Native {
+            denom: "uatom".to_string()
+        }
+    );
+}
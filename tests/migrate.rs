@@ -0,0 +1,295 @@
+mod common;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin, coins, Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError,
+    StdResult, Uint128,
+};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::{Item, Map};
+
+use qtumdao::msg::{
+    ExecuteMsg, MigrateMsg, MintStatsResponse, PositionResponse, QueryMsg, SalePhasesResponse,
+    StateResponse,
+};
+use qtumdao::state::{AssetInfo, SaleKind, TokenIdMode};
+use qtumdao::ContractError;
+
+use common::{dao_contract, ALICE, BOB, OWNER, USDT};
+
+const CAROL: &str = "carol";
+const NINJA: &str = "ninja";
+const SCIENTIST: &str = "scientist";
+
+// storage layout of v0.1.0
+#[cw_serde]
+struct ConfigV0_1 {
+    owner: Addr,
+    scientist_nft_addr: Addr,
+    ninja_nft_addr: Addr,
+    qtum_addr: Addr,
+    xqtum_addr: Addr,
+    nft_price: Uint128,
+    usdt_denom: String,
+    ninja_distribution_schedule: Vec<(u64, u64, Uint128)>,
+    scientist_distribution_schedule: Vec<(u64, u64, Uint128)>,
+}
+
+#[cw_serde]
+struct GuildStateV0_1 {
+    total_rewards_distributed: Uint128,
+    total_staker: u64,
+    total_stake_amount: Uint128,
+    last_distributed: u64,
+    global_reward_index: Decimal,
+}
+
+#[cw_serde]
+struct StakerInfoV0_1 {
+    stake_amount: Uint128,
+    pending_rewards: Uint128,
+    reward_index: Decimal,
+    reward_claimed: Uint128,
+    nft_addr: Option<String>,
+    token_id: Option<String>,
+}
+
+const CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+const NINJA_GUILD_V0_1: Item<GuildStateV0_1> = Item::new("ninja_state");
+const SCIENTIST_GUILD_V0_1: Item<GuildStateV0_1> = Item::new("scientist_state");
+const USER_STAKING_V0_1: Map<&str, StakerInfoV0_1> = Map::new("ninja_user_info");
+
+fn guild_v0_1(total_stake_amount: u128, global_reward_index: Decimal) -> GuildStateV0_1 {
+    GuildStateV0_1 {
+        total_rewards_distributed: Uint128::new(500),
+        total_staker: 2,
+        total_stake_amount: Uint128::new(total_stake_amount),
+        last_distributed: 0,
+        global_reward_index,
+    }
+}
+
+fn staker_v0_1(
+    nft_addr: &str,
+    token_id: &str,
+    stake_amount: u128,
+    pending_rewards: u128,
+    reward_index: Decimal,
+) -> StakerInfoV0_1 {
+    StakerInfoV0_1 {
+        stake_amount: Uint128::new(stake_amount),
+        pending_rewards: Uint128::new(pending_rewards),
+        reward_index,
+        reward_claimed: Uint128::new(5),
+        nft_addr: Some(nft_addr.to_string()),
+        token_id: Some(token_id.to_string()),
+    }
+}
+
+// stands in for the deployed v0.1.0 code, instantiating writes its storage
+fn instantiate_v0_1(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    cw2::set_contract_version(deps.storage, "crates.io:qtuamdao", "0.1.0")?;
+    CONFIG_V0_1.save(
+        deps.storage,
+        &ConfigV0_1 {
+            owner: Addr::unchecked(OWNER),
+            scientist_nft_addr: Addr::unchecked(SCIENTIST),
+            ninja_nft_addr: Addr::unchecked(NINJA),
+            qtum_addr: Addr::unchecked("qtum"),
+            xqtum_addr: Addr::unchecked("xqtum"),
+            nft_price: Uint128::new(10),
+            usdt_denom: USDT.to_string(),
+            ninja_distribution_schedule: vec![],
+            scientist_distribution_schedule: vec![],
+        },
+    )?;
+    NINJA_GUILD_V0_1.save(deps.storage, &guild_v0_1(150, Decimal::percent(50)))?;
+    SCIENTIST_GUILD_V0_1.save(deps.storage, &guild_v0_1(200, Decimal::percent(10)))?;
+    for (staker, info) in [
+        (
+            ALICE,
+            staker_v0_1(NINJA, "1", 100, 30, Decimal::percent(20)),
+        ),
+        (BOB, staker_v0_1(NINJA, "3", 50, 0, Decimal::zero())),
+        (
+            CAROL,
+            staker_v0_1(SCIENTIST, "7", 200, 10, Decimal::percent(10)),
+        ),
+    ] {
+        USER_STAKING_V0_1.save(deps.storage, staker, &info)?;
+    }
+    Ok(Response::new())
+}
+
+fn execute_v0_1(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Err(StdError::generic_err("unsupported"))
+}
+
+fn query_v0_1(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Err(StdError::generic_err("unsupported"))
+}
+
+fn contract_v0_1() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        execute_v0_1,
+        instantiate_v0_1,
+        query_v0_1,
+    ))
+}
+
+fn pending_rewards(app: &App, dao: &Addr, nft_addr: &str, token_id: &str) -> Uint128 {
+    let position: PositionResponse = app
+        .wrap()
+        .query_wasm_smart(
+            dao,
+            &QueryMsg::Position {
+                nft_addr: nft_addr.to_string(),
+                token_id: token_id.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(position.rewards.len(), 1);
+    position.rewards[0].pending_rewards
+}
+
+#[test]
+fn migrate_from_v0_1_keeps_pending_rewards() {
+    let owner = Addr::unchecked(OWNER);
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, vec![coin(1_000, "inj"), coin(1_000, USDT)])
+            .unwrap();
+    });
+    let v0_1_id = app.store_code(contract_v0_1());
+    let dao_id = app.store_code(dao_contract());
+    let dao = app
+        .instantiate_contract(
+            v0_1_id,
+            owner.clone(),
+            &Empty {},
+            &[],
+            "qtumdao",
+            Some(OWNER.to_string()),
+        )
+        .unwrap();
+
+    let msg = MigrateMsg {
+        voting_period: Some(100),
+        quorum: Some(Decimal::percent(30)),
+        threshold: Some(Decimal::percent(50)),
+    };
+    // the governance params are required by this migration and validated
+    let err = app
+        .migrate_contract(
+            owner.clone(),
+            dao.clone(),
+            &MigrateMsg {
+                voting_period: None,
+                ..msg.clone()
+            },
+            dao_id,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::MissingMigrationParams {}
+    );
+    let err = app
+        .migrate_contract(
+            owner.clone(),
+            dao.clone(),
+            &MigrateMsg {
+                voting_period: Some(0),
+                ..msg.clone()
+            },
+            dao_id,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ZeroVotingPeriod {}
+    );
+    app.migrate_contract(owner.clone(), dao.clone(), &msg, dao_id)
+        .unwrap();
+
+    // pending + stake * (global index - staker index)
+    assert_eq!(pending_rewards(&app, &dao, NINJA, "1"), Uint128::new(60));
+    assert_eq!(pending_rewards(&app, &dao, NINJA, "3"), Uint128::new(25));
+    assert_eq!(
+        pending_rewards(&app, &dao, SCIENTIST, "7"),
+        Uint128::new(10)
+    );
+
+    let ninja: StateResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &dao,
+            &QueryMsg::State {
+                nft_addr: NINJA.to_string(),
+                block_time: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(ninja.total_stake_amount, Uint128::new(150));
+    assert_eq!(
+        ninja.rewards[0].asset,
+        AssetInfo::Native {
+            denom: "inj".to_string()
+        }
+    );
+    assert_eq!(ninja.rewards[0].total_rewards_claimed, Uint128::new(415));
+
+    // the migrated rewards are paid out in the asset of each guild
+    app.send_tokens(owner.clone(), dao.clone(), &coins(85, "inj"))
+        .unwrap();
+    app.send_tokens(owner, dao.clone(), &coins(10, USDT))
+        .unwrap();
+    for (staker, denom, expected) in [(ALICE, "inj", 60u128), (BOB, "inj", 25), (CAROL, USDT, 10)] {
+        app.execute_contract(
+            Addr::unchecked(staker),
+            dao.clone(),
+            &ExecuteMsg::Withdraw { recipient: None },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap().query_balance(staker, denom).unwrap().amount,
+            Uint128::new(expected)
+        );
+    }
+    assert_eq!(pending_rewards(&app, &dao, NINJA, "1"), Uint128::zero());
+    assert_eq!(app.wrap().query_all_balances(&dao).unwrap(), vec![]);
+
+    // both collections keep minting chosen ids without a cap
+    for nft_addr in [NINJA, SCIENTIST] {
+        let stats: MintStatsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &dao,
+                &QueryMsg::MintStats {
+                    nft_addr: nft_addr.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(stats.id_mode, TokenIdMode::Chosen);
+        assert_eq!(stats.max_supply, u64::MAX);
+        let phases: SalePhasesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &dao,
+                &QueryMsg::SalePhases {
+                    nft_addr: nft_addr.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(phases.active, Some(1));
+        assert_eq!(phases.phases[0].kind, SaleKind::Public);
+        assert_eq!(phases.phases[0].price, None);
+    }
+}
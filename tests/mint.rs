@@ -0,0 +1,598 @@
+mod common;
+
+use cosmwasm_std::{coin, coins, Addr, Coin, Uint128};
+use cw20::Cw20ExecuteMsg;
+use cw_multi_test::Executor;
+use qtumdao::msg::{
+    CollectionMetadataResponse, ConfigResponse, ExecuteMsg, MintStatsResponse, PhaseMintsResponse,
+    QueryMsg, SalePhasesResponse, TokenUriResponse,
+};
+use qtumdao::state::{MetadataTemplate, MetadataTrait, SaleKind, SalePhase, TokenIdMode};
+use qtumdao::ContractError;
+use sha2::{Digest, Sha256};
+
+use common::{NftExtension, Suite, ALICE, BOB, NATIVE_QTUM, OWNER};
+
+fn leaf(address: &str) -> [u8; 32] {
+    Sha256::digest(address.as_bytes()).into()
+}
+
+// root of the two leaf tree, the pair is hashed in sorted order
+fn merkle_root(first: &str, second: &str) -> String {
+    let (mut a, mut b) = (leaf(first), leaf(second));
+    if b < a {
+        std::mem::swap(&mut a, &mut b);
+    }
+    hex::encode(Sha256::new().chain_update(a).chain_update(b).finalize())
+}
+
+fn proof(sibling: &str) -> Option<Vec<String>> {
+    Some(vec![hex::encode(leaf(sibling))])
+}
+
+fn mint_err(
+    suite: &mut Suite,
+    minter: &str,
+    nft_addr: &Addr,
+    id: Option<&str>,
+    proof: Option<Vec<String>>,
+    amount: u128,
+) -> ContractError {
+    suite
+        .mint(minter, nft_addr, id, proof, amount)
+        .unwrap_err()
+        .downcast()
+        .unwrap()
+}
+
+// a guild collection minted by the dao
+fn collection(suite: &mut Suite, name: &str, max_supply: u64, id_mode: TokenIdMode) -> Addr {
+    let dao = suite.dao.to_string();
+    let nft_addr = suite.create_nft(name, &dao, &[]);
+    suite.add_guild(&nft_addr, None).unwrap();
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::SetCollection {
+                nft_addr: nft_addr.to_string(),
+                max_supply,
+                id_mode,
+            },
+            &[],
+        )
+        .unwrap();
+    nft_addr
+}
+
+fn set_sale_phases(suite: &mut Suite, nft_addr: &Addr, phases: Vec<SalePhase>) {
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::SetSalePhases {
+                nft_addr: nft_addr.to_string(),
+                phases,
+            },
+            &[],
+        )
+        .unwrap();
+}
+
+// public sale from now on, at the config price
+fn public_phase(suite: &Suite, per_address_cap: Option<u32>) -> SalePhase {
+    let now = suite.app.block_info().time.seconds();
+    SalePhase {
+        id: 1,
+        kind: SaleKind::Public,
+        start: now,
+        end: now + 100,
+        price: None,
+        native_price: None,
+        per_address_cap,
+    }
+}
+
+// 100 of the qtum of alice
+fn give_bob_qtum(suite: &mut Suite) {
+    let qtum = suite.qtum.clone();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            qtum,
+            &Cw20ExecuteMsg::Transfer {
+                recipient: BOB.to_string(),
+                amount: Uint128::new(100),
+            },
+            &[],
+        )
+        .unwrap();
+}
+
+fn mint_stats(suite: &Suite, nft_addr: &Addr) -> MintStatsResponse {
+    suite.query(&QueryMsg::MintStats {
+        nft_addr: nft_addr.to_string(),
+    })
+}
+
+// token id the mint was assigned
+fn minted_id(suite: &mut Suite, minter: &str, nft_addr: &Addr) -> String {
+    let res = suite.mint(minter, nft_addr, None, None, 10).unwrap();
+    res.events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == "token_id")
+        .unwrap()
+        .value
+        .clone()
+}
+
+fn nft_owner(suite: &Suite, nft_addr: &Addr, token_id: &str) -> String {
+    let response: cw721::OwnerOfResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            nft_addr,
+            &cw721::Cw721QueryMsg::OwnerOf {
+                token_id: token_id.to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    response.owner
+}
+
+#[test]
+fn allowlist_phase_is_proven_with_a_merkle_proof() {
+    let mut suite = Suite::new();
+    let samurai = collection(&mut suite, "samurai", 10, TokenIdMode::Chosen);
+    let qtum = suite.qtum.clone();
+    give_bob_qtum(&mut suite);
+
+    assert_eq!(
+        mint_err(&mut suite, ALICE, &samurai, Some("1"), None, 10),
+        ContractError::SaleClosed {}
+    );
+
+    let now = suite.app.block_info().time.seconds();
+    set_sale_phases(
+        &mut suite,
+        &samurai,
+        vec![
+            SalePhase {
+                id: 1,
+                kind: SaleKind::Allowlist {
+                    merkle_root: merkle_root(ALICE, BOB),
+                },
+                start: now,
+                end: now + 100,
+                price: Some(Uint128::new(5)),
+                native_price: None,
+                per_address_cap: Some(1),
+            },
+            SalePhase {
+                id: 2,
+                kind: SaleKind::Public,
+                start: now + 100,
+                end: now + 200,
+                price: None,
+                native_price: None,
+                per_address_cap: None,
+            },
+        ],
+    );
+    let phases: SalePhasesResponse = suite.query(&QueryMsg::SalePhases {
+        nft_addr: samurai.to_string(),
+    });
+    assert_eq!(phases.active, Some(1));
+
+    // only listed addresses with a matching proof
+    assert_eq!(
+        mint_err(&mut suite, BOB, &samurai, Some("1"), None, 5),
+        ContractError::InvalidMerkleProof {}
+    );
+    assert_eq!(
+        mint_err(&mut suite, BOB, &samurai, Some("1"), proof(BOB), 5),
+        ContractError::InvalidMerkleProof {}
+    );
+    suite
+        .mint(ALICE, &samurai, Some("1"), proof(BOB), 5)
+        .unwrap();
+    suite
+        .mint(BOB, &samurai, Some("2"), proof(ALICE), 5)
+        .unwrap();
+    assert_eq!(nft_owner(&suite, &samurai, "1"), ALICE);
+    assert_eq!(nft_owner(&suite, &samurai, "2"), BOB);
+    assert_eq!(
+        mint_err(&mut suite, ALICE, &samurai, Some("3"), proof(BOB), 5),
+        ContractError::MintCapReached {}
+    );
+    let minted: PhaseMintsResponse = suite.query(&QueryMsg::PhaseMints {
+        nft_addr: samurai.to_string(),
+        phase_id: 1,
+        address: ALICE.to_string(),
+    });
+    assert_eq!(minted.minted, 1);
+
+    // the public phase falls back to the config price
+    suite.advance(100);
+    assert_eq!(
+        mint_err(&mut suite, BOB, &samurai, Some("3"), None, 5),
+        ContractError::InsufficientToken {}
+    );
+    suite.mint(ALICE, &samurai, Some("3"), None, 10).unwrap();
+    suite.mint(ALICE, &samurai, Some("4"), None, 10).unwrap();
+    assert_eq!(suite.token_balance(&qtum, ALICE), Uint128::new(875));
+    let dao = suite.dao.to_string();
+    assert_eq!(suite.token_balance(&qtum, &dao), Uint128::new(30));
+
+    suite.advance(100);
+    assert_eq!(
+        mint_err(&mut suite, ALICE, &samurai, Some("5"), None, 10),
+        ContractError::SaleClosed {}
+    );
+}
+
+#[test]
+fn only_registered_collections_are_minted_up_to_the_supply() {
+    let mut suite = Suite::new();
+    let (dao, ninja) = (suite.dao.to_string(), suite.ninja.clone());
+    give_bob_qtum(&mut suite);
+
+    // neither unknown collections nor guilds without a collection
+    let monk = suite.create_nft("monk", &dao, &[]);
+    for nft_addr in [&monk, &ninja] {
+        assert_eq!(
+            mint_err(&mut suite, ALICE, nft_addr, Some("1"), None, 10),
+            ContractError::UnsupportedNft {}
+        );
+    }
+
+    let samurai = collection(&mut suite, "samurai", 2, TokenIdMode::Chosen);
+    let phase = public_phase(&suite, Some(1));
+    set_sale_phases(&mut suite, &samurai, vec![phase]);
+    suite.mint(ALICE, &samurai, Some("1"), None, 10).unwrap();
+    suite.mint(BOB, &samurai, Some("2"), None, 10).unwrap();
+    let stats = mint_stats(&suite, &samurai);
+    assert_eq!((stats.max_supply, stats.minted, stats.remaining), (2, 2, 0));
+    assert_eq!(
+        mint_err(&mut suite, ALICE, &samurai, Some("3"), None, 10),
+        ContractError::SoldOut {}
+    );
+
+    // the supply can't drop below the minted tokens
+    let set_collection = |max_supply| ExecuteMsg::SetCollection {
+        nft_addr: samurai.to_string(),
+        max_supply,
+        id_mode: TokenIdMode::Chosen,
+    };
+    let err = suite.execute(OWNER, &set_collection(1), &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidMaxSupply {}
+    );
+    suite.execute(OWNER, &set_collection(3), &[]).unwrap();
+    assert_eq!(
+        mint_err(&mut suite, ALICE, &samurai, Some("3"), None, 10),
+        ContractError::MintCapReached {}
+    );
+
+    // a removed guild is registered again from scratch
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::RemoveGuild {
+                nft_addr: samurai.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        mint_err(&mut suite, ALICE, &samurai, Some("3"), None, 10),
+        ContractError::UnsupportedNft {}
+    );
+    suite.add_guild(&samurai, None).unwrap();
+    suite.execute(OWNER, &set_collection(3), &[]).unwrap();
+    assert_eq!(mint_stats(&suite, &samurai).minted, 0);
+    assert_eq!(
+        mint_err(&mut suite, ALICE, &samurai, Some("3"), None, 10),
+        ContractError::SaleClosed {}
+    );
+    let phase = public_phase(&suite, Some(1));
+    set_sale_phases(&mut suite, &samurai, vec![phase]);
+    let minted: PhaseMintsResponse = suite.query(&QueryMsg::PhaseMints {
+        nft_addr: samurai.to_string(),
+        phase_id: 1,
+        address: ALICE.to_string(),
+    });
+    assert_eq!(minted.minted, 0);
+    suite.mint(ALICE, &samurai, Some("3"), None, 10).unwrap();
+    assert_eq!(nft_owner(&suite, &samurai, "3"), ALICE);
+}
+
+#[test]
+fn token_ids_are_assigned_by_the_contract() {
+    let mut suite = Suite::new();
+    let samurai = collection(&mut suite, "samurai", 3, TokenIdMode::Sequential);
+    let phase = public_phase(&suite, None);
+    set_sale_phases(&mut suite, &samurai, vec![phase]);
+
+    assert_eq!(
+        mint_err(&mut suite, ALICE, &samurai, Some("7"), None, 10),
+        ContractError::InvalidTokenId {}
+    );
+    assert_eq!(minted_id(&mut suite, ALICE, &samurai), "1");
+    assert_eq!(minted_id(&mut suite, ALICE, &samurai), "2");
+    let err = suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::SetCollection {
+                nft_addr: samurai.to_string(),
+                max_supply: 3,
+                id_mode: TokenIdMode::Chosen,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::CollectionLocked {}
+    );
+
+    // shuffled ids are drawn once the committed seed is revealed
+    let seed_hash = hex::encode(Sha256::digest(b"s33d"));
+    let monk = collection(&mut suite, "monk", 3, TokenIdMode::Shuffled { seed_hash });
+    let phase = public_phase(&suite, None);
+    set_sale_phases(&mut suite, &monk, vec![phase]);
+    assert_eq!(
+        mint_err(&mut suite, ALICE, &monk, None, None, 10),
+        ContractError::SeedNotRevealed {}
+    );
+    let reveal_seed = |seed: &str| ExecuteMsg::RevealSeed {
+        nft_addr: monk.to_string(),
+        seed: seed.to_string(),
+    };
+    let err = suite.execute(OWNER, &reveal_seed("seed"), &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidSeed {}
+    );
+    suite.execute(OWNER, &reveal_seed("s33d"), &[]).unwrap();
+    let err = suite.execute(OWNER, &reveal_seed("s33d"), &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::AlreadyRevealed {}
+    );
+    assert_eq!(mint_stats(&suite, &monk).seed, Some("s33d".to_string()));
+
+    let mut ids = vec![];
+    for _ in 0..3 {
+        ids.push(minted_id(&mut suite, ALICE, &monk));
+        suite.advance(1);
+    }
+    ids.sort();
+    assert_eq!(ids, vec!["1", "2", "3"]);
+    assert_eq!(
+        mint_err(&mut suite, ALICE, &monk, None, None, 10),
+        ContractError::SoldOut {}
+    );
+}
+
+fn token_uri(suite: &Suite, nft_addr: &Addr, token_id: &str) -> Option<String> {
+    let response: TokenUriResponse = suite.query(&QueryMsg::TokenUri {
+        nft_addr: nft_addr.to_string(),
+        token_id: token_id.to_string(),
+    });
+    response.token_uri
+}
+
+#[test]
+fn tokens_are_minted_with_uri_and_metadata() {
+    let mut suite = Suite::new();
+    let samurai = collection(&mut suite, "samurai", 3, TokenIdMode::Sequential);
+    let phase = public_phase(&suite, None);
+    set_sale_phases(&mut suite, &samurai, vec![phase]);
+    let reveal = ExecuteMsg::Reveal {
+        nft_addr: samurai.to_string(),
+    };
+    let err = suite.execute(OWNER, &reveal, &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::MissingBaseTokenUri {}
+    );
+
+    let guild_trait = MetadataTrait {
+        trait_type: "guild".to_string(),
+        value: "samurai".to_string(),
+    };
+    let set_metadata = |base_token_uri: &str| ExecuteMsg::SetCollectionMetadata {
+        nft_addr: samurai.to_string(),
+        base_token_uri: Some(base_token_uri.to_string()),
+        placeholder_uri: Some("ipfs://hidden".to_string()),
+        template: Some(MetadataTemplate {
+            name_prefix: Some("Samurai".to_string()),
+            description: None,
+            attributes: vec![guild_trait.clone()],
+        }),
+    };
+    suite
+        .execute(OWNER, &set_metadata("ipfs://base"), &[])
+        .unwrap();
+    suite.mint(ALICE, &samurai, None, None, 10).unwrap();
+    let nft_info: cw721::NftInfoResponse<Option<NftExtension>> = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &samurai,
+            &cw721::Cw721QueryMsg::NftInfo {
+                token_id: "1".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(nft_info.token_uri, Some("ipfs://base/1".to_string()));
+    assert_eq!(
+        nft_info.extension,
+        Some(NftExtension {
+            name: Some("Samurai #1".to_string()),
+            description: None,
+            attributes: Some(vec![guild_trait.clone()]),
+        })
+    );
+    let err = suite
+        .execute(OWNER, &set_metadata("ipfs://other"), &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::CollectionLocked {}
+    );
+
+    // the base token uri stays hidden until the reveal
+    assert_eq!(
+        token_uri(&suite, &samurai, "1"),
+        Some("ipfs://hidden".to_string())
+    );
+    let metadata: CollectionMetadataResponse = suite.query(&QueryMsg::CollectionMetadata {
+        nft_addr: samurai.to_string(),
+    });
+    assert_eq!(metadata.base_token_uri, None);
+    assert!(!metadata.revealed);
+
+    let err = suite.execute(ALICE, &reveal, &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+    suite.execute(OWNER, &reveal, &[]).unwrap();
+    assert_eq!(
+        token_uri(&suite, &samurai, "1"),
+        Some("ipfs://base/1".to_string())
+    );
+    let metadata: CollectionMetadataResponse = suite.query(&QueryMsg::CollectionMetadata {
+        nft_addr: samurai.to_string(),
+    });
+    assert_eq!(metadata.base_token_uri, Some("ipfs://base".to_string()));
+    let err = suite.execute(OWNER, &reveal, &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::AlreadyRevealed {}
+    );
+}
+
+fn native_mint_err(suite: &mut Suite, id: &str, nft_addr: &Addr, amount: u128) -> ContractError {
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::Mint {
+                id: Some(id.to_string()),
+                nft_addr: nft_addr.to_string(),
+                proof: None,
+            },
+            &coins(amount, NATIVE_QTUM),
+        )
+        .map(|_| ())
+        .unwrap_err()
+        .downcast()
+        .unwrap()
+}
+
+fn set_native_price(
+    native_nft_price: Option<Coin>,
+    disable_native_mint: Option<bool>,
+) -> ExecuteMsg {
+    ExecuteMsg::UpdateConfig {
+        qtum_addr: None,
+        xqtum_addr: None,
+        nft_price: None,
+        native_nft_price,
+        disable_native_mint,
+        usdt_denom: None,
+        voting_period: None,
+        quorum: None,
+        threshold: None,
+        lock_tiers: None,
+        unbonding_period: None,
+    }
+}
+
+fn native_balance(suite: &Suite, address: &str) -> Uint128 {
+    suite
+        .app
+        .wrap()
+        .query_balance(address, NATIVE_QTUM)
+        .unwrap()
+        .amount
+}
+
+#[test]
+fn mints_are_paid_in_qtum_or_native_coins_and_refunded() {
+    let mut suite = Suite::new();
+    let (qtum, dao) = (suite.qtum.clone(), suite.dao.to_string());
+    let samurai = collection(&mut suite, "samurai", 5, TokenIdMode::Chosen);
+    let phase = public_phase(&suite, None);
+    set_sale_phases(&mut suite, &samurai, vec![phase.clone()]);
+
+    // the excess of the price is sent back
+    suite.mint(ALICE, &samurai, Some("1"), None, 15).unwrap();
+    assert_eq!(suite.token_balance(&qtum, ALICE), Uint128::new(990));
+    assert_eq!(suite.token_balance(&qtum, &dao), Uint128::new(10));
+
+    assert_eq!(
+        native_mint_err(&mut suite, "2", &samurai, 20),
+        ContractError::NativeMintDisabled {}
+    );
+    suite
+        .execute(
+            OWNER,
+            &set_native_price(Some(coin(20, NATIVE_QTUM)), None),
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        native_mint_err(&mut suite, "2", &samurai, 15),
+        ContractError::InsufficientToken {}
+    );
+    let mint = |id: &str| ExecuteMsg::Mint {
+        id: Some(id.to_string()),
+        nft_addr: samurai.to_string(),
+        proof: None,
+    };
+    suite
+        .execute(ALICE, &mint("2"), &coins(25, NATIVE_QTUM))
+        .unwrap();
+    assert_eq!(native_balance(&suite, ALICE), Uint128::new(980));
+    assert_eq!(native_balance(&suite, &dao), Uint128::new(20));
+    assert_eq!(nft_owner(&suite, &samurai, "2"), ALICE);
+
+    // phases can override the amount of the native price
+    let phase = SalePhase {
+        native_price: Some(Uint128::new(8)),
+        ..phase
+    };
+    set_sale_phases(&mut suite, &samurai, vec![phase]);
+    suite
+        .execute(ALICE, &mint("3"), &coins(8, NATIVE_QTUM))
+        .unwrap();
+    assert_eq!(native_balance(&suite, ALICE), Uint128::new(972));
+
+    // native mints are switched off again by clearing the price
+    let err = suite
+        .execute(
+            OWNER,
+            &set_native_price(Some(coin(20, NATIVE_QTUM)), Some(true)),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NativeMintConflict {}
+    );
+    suite
+        .execute(OWNER, &set_native_price(None, Some(true)), &[])
+        .unwrap();
+    let config: ConfigResponse = suite.query(&QueryMsg::Config {});
+    assert_eq!(config.native_nft_price, None);
+    assert_eq!(
+        native_mint_err(&mut suite, "4", &samurai, 8),
+        ContractError::NativeMintDisabled {}
+    );
+    suite.mint(ALICE, &samurai, Some("4"), None, 10).unwrap();
+}
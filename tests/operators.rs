@@ -0,0 +1,231 @@
+mod common;
+
+use cosmwasm_std::{Timestamp, Uint128};
+use cw_utils::Expiration;
+use qtumdao::msg::{
+    ClaimOperatorsResponse, ExecuteMsg, PositionResponse, ProposalResponse, QueryMsg,
+    RewardRecipientResponse, StakingOperatorsResponse,
+};
+use qtumdao::state::VoteOption;
+use qtumdao::ContractError;
+
+use common::{Suite, ALICE, BOB};
+
+const CAROL: &str = "carol";
+const DAVE: &str = "dave";
+
+fn execute_err(suite: &mut Suite, sender: &str, msg: &ExecuteMsg) -> ContractError {
+    suite
+        .execute(sender, msg, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap()
+}
+
+#[test]
+fn rewards_are_paid_to_the_reward_recipient() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite.distribute(&ninja, 10, 100);
+    suite.advance(5);
+
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::SetRewardRecipient {
+                recipient: Some(CAROL.to_string()),
+            },
+            &[],
+        )
+        .unwrap();
+    let recipient: RewardRecipientResponse = suite.query(&QueryMsg::RewardRecipient {
+        staker: ALICE.to_string(),
+    });
+    assert_eq!(recipient.recipient, CAROL);
+    suite
+        .execute(ALICE, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    assert_eq!(suite.usdt_balance(CAROL), Uint128::new(50));
+
+    // an explicit recipient overrides the setting once
+    suite.advance(5);
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::Withdraw {
+                recipient: Some(DAVE.to_string()),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.usdt_balance(DAVE), Uint128::new(50));
+
+    // claim operators trigger the withdrawal, the rewards still go to the recipient
+    let withdraw_for = ExecuteMsg::WithdrawFor {
+        staker: ALICE.to_string(),
+    };
+    assert_eq!(
+        execute_err(&mut suite, BOB, &withdraw_for),
+        ContractError::Unauthorized {}
+    );
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::ApproveClaimOperator {
+                operator: BOB.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    let operators: ClaimOperatorsResponse = suite.query(&QueryMsg::ClaimOperators {
+        staker: ALICE.to_string(),
+        start_after: None,
+        limit: None,
+    });
+    assert_eq!(operators.operators, vec![BOB.to_string()]);
+    suite.distribute(&ninja, 10, 40);
+    suite.advance(10);
+    suite.execute(BOB, &withdraw_for, &[]).unwrap();
+    assert_eq!(suite.usdt_balance(CAROL), Uint128::new(90));
+    assert_eq!(suite.usdt_balance(BOB), Uint128::zero());
+
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::RevokeClaimOperator {
+                operator: BOB.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        execute_err(&mut suite, BOB, &withdraw_for),
+        ContractError::Unauthorized {}
+    );
+
+    // the final rewards of an unlock follow the setting as well
+    suite.distribute(&ninja, 10, 20);
+    suite.advance(10);
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::Unlock {
+                nft_addr: ninja.to_string(),
+                token_id: "1".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.usdt_balance(CAROL), Uint128::new(110));
+    assert_eq!(suite.usdt_balance(ALICE), Uint128::zero());
+}
+
+#[test]
+fn staking_operator_never_takes_custody() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+
+    let err = suite.stake(BOB, &ninja, "1", 50, None).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+    let expires = suite.app.block_info().time.seconds() + 100;
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::ApproveOperator {
+                operator: BOB.to_string(),
+                expires: Some(Expiration::AtTime(Timestamp::from_seconds(expires))),
+            },
+            &[],
+        )
+        .unwrap();
+
+    // the operator stakes its own xqtum, the unstaked xqtum goes to the staker
+    suite.stake(BOB, &ninja, "1", 50, None).unwrap();
+    let unstake = ExecuteMsg::Unstake {
+        nft_addr: ninja.to_string(),
+        token_id: "1".to_string(),
+        amount: Uint128::new(20),
+    };
+    suite.execute(BOB, &unstake, &[]).unwrap();
+    let position: PositionResponse = suite.query(&QueryMsg::Position {
+        nft_addr: ninja.to_string(),
+        token_id: "1".to_string(),
+    });
+    assert_eq!(position.owner, ALICE);
+    assert_eq!(position.stake_amount, Uint128::new(30));
+    assert_eq!(suite.xqtum_balance(BOB), Uint128::new(950));
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(1020));
+
+    // the nft itself only returns to the owner
+    assert_eq!(
+        execute_err(
+            &mut suite,
+            BOB,
+            &ExecuteMsg::Unlock {
+                nft_addr: ninja.to_string(),
+                token_id: "1".to_string(),
+            }
+        ),
+        ContractError::Unauthorized {}
+    );
+
+    suite.distribute(&ninja, 10, 30);
+    suite.advance(10);
+    suite
+        .execute(
+            BOB,
+            &ExecuteMsg::WithdrawFor {
+                staker: ALICE.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.usdt_balance(ALICE), Uint128::new(30));
+    assert_eq!(suite.usdt_balance(BOB), Uint128::zero());
+
+    // the operator votes with the weight of the staker
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::Propose {
+                title: "title".to_string(),
+                description: "description".to_string(),
+                msgs: vec![],
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+        .execute(
+            BOB,
+            &ExecuteMsg::VoteFor {
+                proposal_id: 1,
+                vote: VoteOption::Yes,
+                staker: ALICE.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    let proposal: ProposalResponse = suite.query(&QueryMsg::Proposal { proposal_id: 1 });
+    assert_eq!(proposal.yes_votes, Uint128::new(30));
+
+    // the approval ends with its expiry
+    suite.advance(100);
+    assert_eq!(
+        execute_err(&mut suite, BOB, &unstake),
+        ContractError::Unauthorized {}
+    );
+    let operators: StakingOperatorsResponse = suite.query(&QueryMsg::StakingOperators {
+        staker: ALICE.to_string(),
+        include_expired: None,
+        start_after: None,
+        limit: None,
+    });
+    assert!(operators.operators.is_empty());
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use cosmwasm_std::{Addr, Uint128};
+use qtumdao::msg::{ExecuteMsg, QueryMsg, SolvencyResponse, StakerInfoResponse, StateResponse};
+use qtumdao::state::Role;
+use qtumdao::ContractError;
+
+use common::{Suite, ALICE, BOB, OWNER};
+
+fn execute_err(suite: &mut Suite, sender: &str, msg: &ExecuteMsg) -> ContractError {
+    suite
+        .execute(sender, msg, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap()
+}
+
+fn set_pause(unstake: bool, withdraw: bool) -> ExecuteMsg {
+    ExecuteMsg::SetPause {
+        mint: None,
+        lock: None,
+        stake: None,
+        unstake: Some(unstake),
+        withdraw: Some(withdraw),
+    }
+}
+
+fn unlock(nft_addr: &Addr, token_id: &str) -> ExecuteMsg {
+    ExecuteMsg::Unlock {
+        nft_addr: nft_addr.to_string(),
+        token_id: token_id.to_string(),
+    }
+}
+
+fn emergency_unlock(nft_addr: &Addr, token_id: &str) -> ExecuteMsg {
+    ExecuteMsg::EmergencyUnlock {
+        nft_addr: nft_addr.to_string(),
+        token_id: token_id.to_string(),
+    }
+}
+
+#[test]
+fn paused_entry_points_are_rejected() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::GrantRole {
+                role: Role::Pauser,
+                address: BOB.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    let pause_lock = ExecuteMsg::SetPause {
+        mint: None,
+        lock: Some(true),
+        stake: Some(true),
+        unstake: None,
+        withdraw: None,
+    };
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &pause_lock),
+        ContractError::Unauthorized {}
+    );
+    suite.execute(BOB, &pause_lock, &[]).unwrap();
+    let err = suite.lock(ALICE, &ninja, "1").unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Paused {}
+    );
+
+    suite
+        .execute(
+            BOB,
+            &ExecuteMsg::SetPause {
+                mint: None,
+                lock: Some(false),
+                stake: None,
+                unstake: None,
+                withdraw: None,
+            },
+            &[],
+        )
+        .unwrap();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    let err = suite.stake(ALICE, &ninja, "1", 100, None).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Paused {}
+    );
+
+    // unlocking pays the rewards, so it stops with the withdrawals too
+    suite.execute(BOB, &set_pause(false, true), &[]).unwrap();
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &unlock(&ninja, "1")),
+        ContractError::Paused {}
+    );
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &ExecuteMsg::Withdraw { recipient: None }),
+        ContractError::Paused {}
+    );
+    suite.execute(BOB, &set_pause(false, false), &[]).unwrap();
+    suite.execute(ALICE, &unlock(&ninja, "1"), &[]).unwrap();
+}
+
+#[test]
+fn emergency_unlock_returns_the_stake_and_forfeits_the_rewards() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::UpdateConfig {
+                qtum_addr: None,
+                xqtum_addr: None,
+                nft_price: None,
+                native_nft_price: None,
+                disable_native_mint: None,
+                usdt_denom: None,
+                voting_period: None,
+                quorum: None,
+                threshold: None,
+                lock_tiers: None,
+                unbonding_period: Some(50),
+            },
+            &[],
+        )
+        .unwrap();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.lock(BOB, &ninja, "3").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite.stake(BOB, &ninja, "3", 100, None).unwrap();
+    suite.distribute(&ninja, 10, 200);
+    suite.advance(10);
+    suite
+        .execute(BOB, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+
+    // only while both exits are paused
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &emergency_unlock(&ninja, "1")),
+        ContractError::NotPaused {}
+    );
+    suite.execute(OWNER, &set_pause(true, false), &[]).unwrap();
+    assert_eq!(
+        execute_err(&mut suite, ALICE, &emergency_unlock(&ninja, "1")),
+        ContractError::NotPaused {}
+    );
+    suite.execute(OWNER, &set_pause(true, true), &[]).unwrap();
+    assert_eq!(
+        execute_err(&mut suite, BOB, &emergency_unlock(&ninja, "1")),
+        ContractError::Unauthorized {}
+    );
+
+    // the xqtum skips the unbonding queue that can't be claimed while paused
+    suite
+        .execute(ALICE, &emergency_unlock(&ninja, "1"), &[])
+        .unwrap();
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(1000));
+    let owner: cw721::OwnerOfResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &ninja,
+            &cw721::Cw721QueryMsg::OwnerOf {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(owner.owner, ALICE);
+    let alice: StakerInfoResponse = suite.query(&QueryMsg::StakerInfo {
+        staker: ALICE.to_string(),
+    });
+    assert_eq!((alice.stake_amount, alice.positions), (Uint128::zero(), 0));
+    let guild: StateResponse = suite.query(&QueryMsg::State {
+        nft_addr: ninja.to_string(),
+        block_time: None,
+    });
+    assert_eq!(guild.total_staker, 1);
+    assert_eq!(guild.total_stake_amount, Uint128::new(100));
+
+    // the 100 usdt alice forfeited are no longer committed
+    assert_eq!(
+        guild.rewards[0].total_rewards_distributed,
+        Uint128::new(100)
+    );
+    assert_eq!(guild.rewards[0].total_rewards_claimed, Uint128::new(100));
+    let solvency: SolvencyResponse = suite.query(&QueryMsg::Solvency {});
+    assert_eq!(solvency.assets[0].committed, Uint128::zero());
+    assert_eq!(solvency.assets[0].balance, Uint128::new(100));
+}
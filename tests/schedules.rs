@@ -0,0 +1,355 @@
+mod common;
+
+use cosmwasm_std::{coins, to_json_binary, Addr, Uint128};
+use cw20::Cw20ExecuteMsg;
+use cw_multi_test::Executor;
+use cw_utils::PaymentError;
+use qtumdao::msg::{
+    AssetSolvency, Cw20HookMsg, ExecuteMsg, PositionResponse, QueryMsg, ScheduleResponse,
+    SchedulesResponse, SolvencyResponse,
+};
+use qtumdao::state::AssetInfo;
+use qtumdao::ContractError;
+
+use common::{Suite, ALICE, BOB, OWNER, USDT};
+
+fn position(suite: &Suite, nft_addr: &Addr, token_id: &str) -> PositionResponse {
+    suite.query(&QueryMsg::Position {
+        nft_addr: nft_addr.to_string(),
+        token_id: token_id.to_string(),
+    })
+}
+
+// the owner holds 500 qtum to fund cw20 schedules with
+fn qtum_funded_suite() -> Suite {
+    let mut suite = Suite::new();
+    let qtum = suite.qtum.clone();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            qtum,
+            &Cw20ExecuteMsg::Transfer {
+                recipient: OWNER.to_string(),
+                amount: Uint128::new(500),
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+}
+
+#[test]
+fn withdraw_pays_every_reward_asset() {
+    let mut suite = qtum_funded_suite();
+    let (ninja, qtum) = (suite.ninja.clone(), suite.qtum.clone());
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.lock(BOB, &ninja, "3").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite.stake(BOB, &ninja, "3", 100, None).unwrap();
+
+    // usdt plus a qtum co-incentive on the same pool
+    suite.distribute(&ninja, 10, 200);
+    suite.fund(OWNER, &qtum, &ninja, 20, 100).unwrap();
+    suite.advance(10);
+
+    suite
+        .execute(ALICE, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    assert_eq!(suite.usdt_balance(ALICE), Uint128::new(100));
+    assert_eq!(suite.token_balance(&qtum, ALICE), Uint128::new(525));
+    let rewards = position(&suite, &ninja, "1").rewards;
+    assert_eq!(rewards.len(), 2);
+    assert_eq!(
+        rewards[1].asset,
+        AssetInfo::Cw20 {
+            contract_addr: qtum.clone()
+        }
+    );
+    assert_eq!(rewards[1].reward_claimed, Uint128::new(25));
+
+    suite.advance(10);
+    for (staker, qtum_balance) in [(ALICE, 550u128), (BOB, 50)] {
+        suite
+            .execute(staker, &ExecuteMsg::Withdraw { recipient: None }, &[])
+            .unwrap();
+        assert_eq!(suite.usdt_balance(staker), Uint128::new(100));
+        assert_eq!(
+            suite.token_balance(&qtum, staker),
+            Uint128::new(qtum_balance)
+        );
+    }
+    assert!(position(&suite, &ninja, "3")
+        .rewards
+        .iter()
+        .all(|reward| reward.pending_rewards.is_zero()));
+}
+
+#[test]
+fn schedules_have_to_be_funded() {
+    let mut suite = qtum_funded_suite();
+    let (ninja, qtum) = (suite.ninja.clone(), suite.qtum.clone());
+    let start_date = suite.app.block_info().time.seconds();
+    let set_distribution = ExecuteMsg::SetDistribution {
+        nft_addr: ninja.to_string(),
+        asset: None,
+        start_date,
+        end_date: start_date + 10,
+        amount: Uint128::new(200),
+    };
+    let err = suite
+        .execute(OWNER, &set_distribution, &coins(150, USDT))
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ScheduleFundsMismatch {}
+    );
+    suite.execute(OWNER, &set_distribution, &[]).unwrap_err();
+    suite
+        .execute(OWNER, &set_distribution, &coins(200, USDT))
+        .unwrap();
+    suite.fund(OWNER, &qtum, &ninja, 10, 100).unwrap();
+
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite.advance(5);
+    suite
+        .execute(ALICE, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+
+    // what is scheduled but not claimed yet is covered by the balance
+    let solvency: SolvencyResponse = suite.query(&QueryMsg::Solvency {});
+    assert_eq!(
+        solvency.assets,
+        vec![
+            AssetSolvency {
+                asset: AssetInfo::Native {
+                    denom: USDT.to_string()
+                },
+                committed: Uint128::new(100),
+                balance: Uint128::new(100),
+                solvent: true,
+            },
+            AssetSolvency {
+                asset: AssetInfo::Cw20 {
+                    contract_addr: qtum
+                },
+                committed: Uint128::new(50),
+                balance: Uint128::new(50),
+                solvent: true,
+            },
+        ]
+    );
+}
+
+fn schedules(suite: &Suite, nft_addr: &Addr) -> Vec<ScheduleResponse> {
+    let response: SchedulesResponse = suite.query(&QueryMsg::Schedules {
+        nft_addr: nft_addr.to_string(),
+        start_after: None,
+        limit: None,
+    });
+    response.schedules
+}
+
+fn staked_suite() -> Suite {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite
+}
+
+#[test]
+fn cancelled_schedule_refunds_the_unreleased_part() {
+    let mut suite = staked_suite();
+    let ninja = suite.ninja.clone();
+    suite.distribute(&ninja, 10, 100);
+    let start_date = suite.app.block_info().time.seconds() + 20;
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::SetDistribution {
+                nft_addr: ninja.to_string(),
+                asset: None,
+                start_date,
+                end_date: start_date + 10,
+                amount: Uint128::new(30),
+            },
+            &coins(30, USDT),
+        )
+        .unwrap();
+    suite.advance(4);
+
+    let cancel = |schedule_id: u64| ExecuteMsg::CancelSchedule { schedule_id };
+    let err = suite.execute(ALICE, &cancel(1), &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+
+    // the running schedule ends now with what it released so far
+    suite.execute(OWNER, &cancel(1), &[]).unwrap();
+    assert_eq!(suite.usdt_balance(OWNER), Uint128::new(1_000_000 - 70));
+    // a schedule that hasn't started is refunded completely
+    suite.execute(OWNER, &cancel(2), &[]).unwrap();
+    assert_eq!(suite.usdt_balance(OWNER), Uint128::new(1_000_000 - 40));
+    let now = suite.app.block_info().time.seconds();
+    let remaining = schedules(&suite, &ninja);
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].end_date, now);
+    assert_eq!(remaining[0].amount, Uint128::new(40));
+
+    suite.advance(20);
+    let err = suite.execute(OWNER, &cancel(1), &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::ScheduleFinished {}
+    );
+    suite
+        .execute(ALICE, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    assert_eq!(suite.usdt_balance(ALICE), Uint128::new(40));
+    let solvency: SolvencyResponse = suite.query(&QueryMsg::Solvency {});
+    assert_eq!(solvency.assets[0].committed, Uint128::zero());
+    assert_eq!(solvency.assets[0].balance, Uint128::zero());
+}
+
+#[test]
+fn updated_schedule_releases_the_rest_until_the_new_end() {
+    let mut suite = staked_suite();
+    let ninja = suite.ninja.clone();
+    suite.distribute(&ninja, 10, 100);
+    suite.advance(5);
+
+    let now = suite.app.block_info().time.seconds();
+    let update = |end_date: u64| ExecuteMsg::UpdateSchedule {
+        schedule_id: 1,
+        end_date,
+    };
+    let err = suite.execute(OWNER, &update(now), &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidScheduleRange {}
+    );
+
+    // the other 50 are stretched over the next 20 seconds
+    suite.execute(OWNER, &update(now + 20), &[]).unwrap();
+    let schedule = &schedules(&suite, &ninja)[0];
+    assert_eq!(
+        (schedule.start_date, schedule.end_date, schedule.amount),
+        (now, now + 20, Uint128::new(50))
+    );
+    suite.advance(10);
+    suite
+        .execute(ALICE, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    assert_eq!(suite.usdt_balance(ALICE), Uint128::new(75));
+
+    // shortened again, the rest is released by the new end
+    let now = suite.app.block_info().time.seconds();
+    suite.execute(OWNER, &update(now + 2), &[]).unwrap();
+    suite.advance(2);
+    suite
+        .execute(ALICE, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    assert_eq!(suite.usdt_balance(ALICE), Uint128::new(100));
+}
+
+#[test]
+fn pruned_schedules_keep_the_released_rewards() {
+    let mut suite = staked_suite();
+    let ninja = suite.ninja.clone();
+    suite.distribute(&ninja, 10, 100);
+    suite.advance(5);
+    suite.distribute(&ninja, 10, 50);
+    suite.advance(5);
+
+    let prune = ExecuteMsg::PruneSchedules {
+        nft_addr: ninja.to_string(),
+    };
+    suite.execute(ALICE, &prune, &[]).unwrap_err();
+    suite.execute(OWNER, &prune, &[]).unwrap();
+    let remaining = schedules(&suite, &ninja);
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].id, 2);
+
+    suite.advance(5);
+    suite.execute(OWNER, &prune, &[]).unwrap();
+    assert!(schedules(&suite, &ninja).is_empty());
+    suite
+        .execute(ALICE, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    assert_eq!(suite.usdt_balance(ALICE), Uint128::new(150));
+}
+
+#[test]
+fn invalid_schedules_are_rejected() {
+    let mut suite = qtum_funded_suite();
+    let (ninja, qtum) = (suite.ninja.clone(), suite.qtum.clone());
+    suite.advance(100);
+    let now = suite.app.block_info().time.seconds();
+
+    for (start_date, end_date, amount, expected) in [
+        (
+            now + 10,
+            now + 10,
+            100u128,
+            ContractError::InvalidScheduleRange {},
+        ),
+        (
+            now + 10,
+            now + 5,
+            100,
+            ContractError::InvalidScheduleRange {},
+        ),
+        (
+            now - 1,
+            now + 10,
+            100,
+            ContractError::ScheduleStartInPast {},
+        ),
+        // nothing attached for a zero amount
+        (
+            now,
+            now + 10,
+            0,
+            ContractError::Payment(PaymentError::NoFunds {}),
+        ),
+    ] {
+        let msg = ExecuteMsg::SetDistribution {
+            nft_addr: ninja.to_string(),
+            asset: None,
+            start_date,
+            end_date,
+            amount: Uint128::new(amount),
+        };
+        let funds = if amount == 0 {
+            vec![]
+        } else {
+            coins(amount, USDT)
+        };
+        let err = suite.execute(OWNER, &msg, &funds).unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), expected);
+    }
+
+    // cw20 funded schedules are validated the same way
+    let msg = Cw20ExecuteMsg::Send {
+        contract: suite.dao.to_string(),
+        amount: Uint128::new(100),
+        msg: to_json_binary(&Cw20HookMsg::FundSchedule {
+            nft_addr: ninja.to_string(),
+            start_date: now + 10,
+            end_date: now,
+        })
+        .unwrap(),
+    };
+    let err = suite
+        .app
+        .execute_contract(Addr::unchecked(OWNER), qtum, &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::InvalidScheduleRange {}
+    );
+    assert!(schedules(&suite, &ninja).is_empty());
+}
@@ -0,0 +1,520 @@
+mod common;
+
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw20::Cw20ExecuteMsg;
+use cw_multi_test::Executor;
+use qtumdao::msg::{
+    ExecuteMsg, PositionResponse, QueryMsg, StakerInfoResponse, StateResponse, TokenMultiplier,
+    TraitMultiplier, VotingPowerAtHeightResponse,
+};
+use qtumdao::state::{AssetInfo, MetadataTrait};
+use qtumdao::ContractError;
+
+use common::{NftExtension, Suite, ALICE, BOB, LOCK_DURATION, OWNER};
+
+fn staker_info(suite: &Suite, staker: &str) -> StakerInfoResponse {
+    suite.query(&QueryMsg::StakerInfo {
+        staker: staker.to_string(),
+    })
+}
+
+fn state(suite: &Suite, nft_addr: &Addr) -> StateResponse {
+    suite.query(&QueryMsg::State {
+        nft_addr: nft_addr.to_string(),
+        block_time: None,
+    })
+}
+
+fn position(suite: &Suite, nft_addr: &Addr, token_id: &str) -> PositionResponse {
+    suite.query(&QueryMsg::Position {
+        nft_addr: nft_addr.to_string(),
+        token_id: token_id.to_string(),
+    })
+}
+
+#[test]
+fn lock_stake_unstake_unlock_totals() {
+    let mut suite = Suite::new();
+    let (ninja, scientist) = (suite.ninja.clone(), suite.scientist.clone());
+
+    // staking needs a locked nft of the staker
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap_err();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.lock(ALICE, &scientist, "7").unwrap();
+    suite.lock(BOB, &ninja, "3").unwrap();
+    suite.stake(BOB, &ninja, "1", 100, None).unwrap_err();
+
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite.stake(ALICE, &scientist, "7", 50, None).unwrap();
+    suite.stake(BOB, &ninja, "3", 70, None).unwrap();
+
+    let alice = staker_info(&suite, ALICE);
+    assert_eq!(alice.stake_amount, Uint128::new(150));
+    assert_eq!(alice.voting_power, Uint128::new(150));
+    assert_eq!(alice.positions, 2);
+    let guild = state(&suite, &ninja);
+    assert_eq!(guild.total_staker, 2);
+    assert_eq!(guild.total_stake_amount, Uint128::new(170));
+    assert_eq!(guild.total_effective_stake, Uint128::new(170));
+    assert_eq!(
+        state(&suite, &scientist).total_stake_amount,
+        Uint128::new(50)
+    );
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(850));
+
+    let unstake = |amount: u128| ExecuteMsg::Unstake {
+        nft_addr: ninja.to_string(),
+        token_id: "1".to_string(),
+        amount: Uint128::new(amount),
+    };
+    suite.execute(ALICE, &unstake(101), &[]).unwrap_err();
+    suite.execute(BOB, &unstake(10), &[]).unwrap_err();
+    suite.execute(ALICE, &unstake(40), &[]).unwrap();
+    assert_eq!(position(&suite, &ninja, "1").stake_amount, Uint128::new(60));
+    assert_eq!(state(&suite, &ninja).total_stake_amount, Uint128::new(130));
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(890));
+
+    // unlocking returns the remaining stake and the nft
+    let unlock = ExecuteMsg::Unlock {
+        nft_addr: ninja.to_string(),
+        token_id: "1".to_string(),
+    };
+    suite.execute(BOB, &unlock, &[]).unwrap_err();
+    suite.execute(ALICE, &unlock, &[]).unwrap();
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(950));
+
+    let alice = staker_info(&suite, ALICE);
+    assert_eq!(alice.stake_amount, Uint128::new(50));
+    assert_eq!(alice.voting_power, Uint128::new(50));
+    assert_eq!(alice.positions, 1);
+    let guild = state(&suite, &ninja);
+    assert_eq!(guild.total_staker, 1);
+    assert_eq!(guild.total_stake_amount, Uint128::new(70));
+    assert_eq!(guild.total_effective_stake, Uint128::new(70));
+
+    let owner: cw721::OwnerOfResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &ninja,
+            &cw721::Cw721QueryMsg::OwnerOf {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(owner.owner, ALICE);
+}
+
+#[test]
+fn rewards_split_by_multiplier() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::SetTokenMultipliers {
+                nft_addr: ninja.to_string(),
+                multipliers: vec![TokenMultiplier {
+                    token_id: "1".to_string(),
+                    multiplier: Some(Decimal::percent(300)),
+                }],
+            },
+            &[],
+        )
+        .unwrap();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.lock(BOB, &ninja, "3").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite.stake(BOB, &ninja, "3", 100, None).unwrap();
+    assert_eq!(
+        position(&suite, &ninja, "1").effective_stake,
+        Uint128::new(300)
+    );
+    assert_eq!(
+        state(&suite, &ninja).total_effective_stake,
+        Uint128::new(400)
+    );
+
+    suite.distribute(&ninja, 10, 400);
+    suite.advance(20);
+
+    // 3x against 1x of the same stake
+    for (staker, expected) in [(ALICE, 300u128), (BOB, 100)] {
+        suite
+            .execute(staker, &ExecuteMsg::Withdraw { recipient: None }, &[])
+            .unwrap();
+        assert_eq!(suite.usdt_balance(staker), Uint128::new(expected));
+    }
+}
+
+fn nft_traits(traits: &[(&str, &str)]) -> Option<NftExtension> {
+    Some(NftExtension {
+        attributes: Some(
+            traits
+                .iter()
+                .map(|(trait_type, value)| MetadataTrait {
+                    trait_type: trait_type.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+        ),
+        ..NftExtension::default()
+    })
+}
+
+#[test]
+fn multiplier_is_taken_from_the_nft_traits() {
+    let mut suite = Suite::new();
+    let samurai = suite.create_nft("samurai", OWNER, &[]);
+    suite.mint_nft(&samurai, "9", BOB, nft_traits(&[("rarity", "legendary")]));
+    suite.mint_nft(
+        &samurai,
+        "10",
+        ALICE,
+        nft_traits(&[("rarity", "common"), ("background", "gold")]),
+    );
+    suite.mint_nft(&samurai, "11", ALICE, None);
+    suite.add_guild(&samurai, None).unwrap();
+
+    let trait_multiplier = |trait_type: &str, value: &str, percent: Option<u64>| TraitMultiplier {
+        trait_type: trait_type.to_string(),
+        value: value.to_string(),
+        multiplier: percent.map(Decimal::percent),
+    };
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::SetTraitMultipliers {
+                nft_addr: samurai.to_string(),
+                multipliers: vec![
+                    trait_multiplier("rarity", "legendary", Some(300)),
+                    trait_multiplier("rarity", "common", Some(150)),
+                    trait_multiplier("background", "gold", Some(200)),
+                ],
+            },
+            &[],
+        )
+        .unwrap();
+    for (owner, token_id) in [(BOB, "9"), (ALICE, "10"), (ALICE, "11")] {
+        suite.lock(owner, &samurai, token_id).unwrap();
+        suite.stake(owner, &samurai, token_id, 100, None).unwrap();
+    }
+
+    // the highest trait counts, nfts without traits stay at one
+    for (token_id, expected) in [("9", 300u128), ("10", 200), ("11", 100)] {
+        assert_eq!(
+            position(&suite, &samurai, token_id).effective_stake,
+            Uint128::new(expected)
+        );
+    }
+    assert_eq!(
+        state(&suite, &samurai).total_effective_stake,
+        Uint128::new(600)
+    );
+
+    // a token entry takes precedence and re-weights the locked nft right away
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::SetTokenMultipliers {
+                nft_addr: samurai.to_string(),
+                multipliers: vec![TokenMultiplier {
+                    token_id: "9".to_string(),
+                    multiplier: Some(Decimal::one()),
+                }],
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        position(&suite, &samurai, "9").effective_stake,
+        Uint128::new(100)
+    );
+    assert_eq!(
+        state(&suite, &samurai).total_effective_stake,
+        Uint128::new(400)
+    );
+}
+
+#[test]
+fn rewards_split_by_lock_tier() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.lock(BOB, &ninja, "3").unwrap();
+    suite
+        .stake(ALICE, &ninja, "1", 100, Some(LOCK_DURATION))
+        .unwrap();
+    suite.stake(BOB, &ninja, "3", 100, None).unwrap();
+    let alice = position(&suite, &ninja, "1");
+    assert_eq!(alice.effective_stake, Uint128::new(200));
+    assert_eq!(alice.voting_power, Uint128::new(300));
+
+    // the boost ends with the lock: 2:1 for the first half, 1:1 afterwards
+    suite.distribute(&ninja, 2 * LOCK_DURATION, 300);
+    suite.advance(2 * LOCK_DURATION);
+
+    let height = suite.app.block_info().height;
+    let voting_power: VotingPowerAtHeightResponse = suite.query(&QueryMsg::VotingPowerAtHeight {
+        address: ALICE.to_string(),
+        height,
+        time: None,
+    });
+    assert_eq!(voting_power.voting_power, Uint128::new(100));
+
+    for (staker, expected) in [(ALICE, 175u128), (BOB, 125)] {
+        suite
+            .execute(staker, &ExecuteMsg::Withdraw { recipient: None }, &[])
+            .unwrap();
+        assert_eq!(suite.usdt_balance(staker), Uint128::new(expected));
+    }
+    let guild = state(&suite, &ninja);
+    assert_eq!(guild.total_effective_stake, Uint128::new(200));
+    assert_eq!(guild.total_voting_power, Uint128::new(200));
+}
+
+#[test]
+fn locked_stake_cannot_leave_early() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite
+        .stake(ALICE, &ninja, "1", 50, Some(LOCK_DURATION - 1))
+        .unwrap_err();
+    suite
+        .stake(ALICE, &ninja, "1", 50, Some(LOCK_DURATION))
+        .unwrap();
+
+    let unstake = |amount: u128| ExecuteMsg::Unstake {
+        nft_addr: ninja.to_string(),
+        token_id: "1".to_string(),
+        amount: Uint128::new(amount),
+    };
+    let unlock = ExecuteMsg::Unlock {
+        nft_addr: ninja.to_string(),
+        token_id: "1".to_string(),
+    };
+    suite.execute(ALICE, &unstake(101), &[]).unwrap_err();
+    suite.execute(ALICE, &unlock, &[]).unwrap_err();
+
+    suite.advance(LOCK_DURATION);
+    suite.execute(ALICE, &unstake(150), &[]).unwrap();
+    suite.execute(ALICE, &unlock, &[]).unwrap();
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(1000));
+    assert_eq!(staker_info(&suite, ALICE).positions, 0);
+}
+
+#[test]
+fn emergency_unlock_releases_running_locks() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.lock(BOB, &ninja, "3").unwrap();
+    suite
+        .stake(ALICE, &ninja, "1", 100, Some(LOCK_DURATION))
+        .unwrap();
+    suite.stake(BOB, &ninja, "3", 100, None).unwrap();
+    suite.distribute(&ninja, 2 * LOCK_DURATION, 300);
+    suite.advance(LOCK_DURATION / 2);
+
+    let set_pause = |paused: bool| ExecuteMsg::SetPause {
+        mint: None,
+        lock: None,
+        stake: None,
+        unstake: Some(paused),
+        withdraw: Some(paused),
+    };
+    suite.execute(OWNER, &set_pause(true), &[]).unwrap();
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::EmergencyUnlock {
+                nft_addr: ninja.to_string(),
+                token_id: "1".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(1000));
+    let alice = staker_info(&suite, ALICE);
+    assert_eq!(alice.voting_power, Uint128::zero());
+    let guild = state(&suite, &ninja);
+    assert_eq!(guild.total_effective_stake, Uint128::new(100));
+    assert_eq!(guild.total_voting_power, Uint128::new(100));
+
+    // the released lock no longer ends in the guild totals, bob earns the rest alone
+    suite.execute(OWNER, &set_pause(false), &[]).unwrap();
+    suite.advance(2 * LOCK_DURATION);
+    suite
+        .execute(BOB, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    let guild = state(&suite, &ninja);
+    assert_eq!(guild.total_effective_stake, Uint128::new(100));
+    assert_eq!(guild.total_voting_power, Uint128::new(100));
+    // nothing was distributed before the emergency exit, so bob earns everything
+    assert_eq!(suite.usdt_balance(BOB), Uint128::new(300));
+}
+
+#[test]
+fn unbonding_stake_is_claimed_later() {
+    let mut suite = Suite::new();
+    let ninja = suite.ninja.clone();
+
+    suite
+        .execute(
+            OWNER,
+            &ExecuteMsg::UpdateConfig {
+                qtum_addr: None,
+                xqtum_addr: None,
+                nft_price: None,
+                native_nft_price: None,
+                disable_native_mint: None,
+                usdt_denom: None,
+                voting_period: None,
+                quorum: None,
+                threshold: None,
+                lock_tiers: None,
+                unbonding_period: Some(50),
+            },
+            &[],
+        )
+        .unwrap();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::Unstake {
+                nft_addr: ninja.to_string(),
+                token_id: "1".to_string(),
+                amount: Uint128::new(30),
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+        .execute(
+            ALICE,
+            &ExecuteMsg::Unlock {
+                nft_addr: ninja.to_string(),
+                token_id: "1".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(state(&suite, &ninja).total_stake_amount, Uint128::zero());
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(900));
+
+    let claims: cw_controllers::ClaimsResponse = suite.query(&QueryMsg::Claims {
+        address: ALICE.to_string(),
+    });
+    assert_eq!(claims.claims.len(), 2);
+    suite
+        .execute(ALICE, &ExecuteMsg::ClaimUnbonded {}, &[])
+        .unwrap_err();
+
+    suite.advance(50);
+    suite
+        .execute(ALICE, &ExecuteMsg::ClaimUnbonded {}, &[])
+        .unwrap();
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(1000));
+}
+
+#[test]
+fn compound_stakes_the_xqtum_rewards() {
+    let mut suite = Suite::new();
+    let (ninja, xqtum) = (suite.ninja.clone(), suite.xqtum.clone());
+    let samurai = suite.create_nft("samurai", OWNER, &[("9", ALICE)]);
+    suite
+        .add_guild(
+            &samurai,
+            Some(AssetInfo::Cw20 {
+                contract_addr: xqtum.clone(),
+            }),
+        )
+        .unwrap();
+    suite.lock(ALICE, &ninja, "1").unwrap();
+    suite.lock(ALICE, &samurai, "9").unwrap();
+    suite.stake(ALICE, &ninja, "1", 100, None).unwrap();
+    suite.stake(ALICE, &samurai, "9", 100, None).unwrap();
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BOB),
+            xqtum.clone(),
+            &Cw20ExecuteMsg::Transfer {
+                recipient: OWNER.to_string(),
+                amount: Uint128::new(100),
+            },
+            &[],
+        )
+        .unwrap();
+    suite.fund(OWNER, &xqtum, &samurai, 10, 100).unwrap();
+    suite.distribute(&ninja, 10, 50);
+    suite.advance(10);
+
+    // compounding claims the rewards, it stops with the withdrawals
+    let pause_withdraw = |paused: bool| ExecuteMsg::SetPause {
+        mint: None,
+        lock: None,
+        stake: None,
+        unstake: None,
+        withdraw: Some(paused),
+    };
+    suite.execute(OWNER, &pause_withdraw(true), &[]).unwrap();
+    let err = suite
+        .execute(ALICE, &ExecuteMsg::Compound {}, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Paused {}
+    );
+    suite.execute(OWNER, &pause_withdraw(false), &[]).unwrap();
+
+    suite.execute(ALICE, &ExecuteMsg::Compound {}, &[]).unwrap();
+    assert_eq!(
+        position(&suite, &samurai, "9").stake_amount,
+        Uint128::new(200)
+    );
+    assert_eq!(
+        state(&suite, &samurai).total_stake_amount,
+        Uint128::new(200)
+    );
+    assert_eq!(staker_info(&suite, ALICE).stake_amount, Uint128::new(300));
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(800));
+
+    // the usdt rewards can't be converted and stay pending
+    assert_eq!(
+        position(&suite, &ninja, "1").stake_amount,
+        Uint128::new(100)
+    );
+    suite
+        .execute(ALICE, &ExecuteMsg::Withdraw { recipient: None }, &[])
+        .unwrap();
+    assert_eq!(suite.usdt_balance(ALICE), Uint128::new(50));
+    assert_eq!(suite.xqtum_balance(ALICE), Uint128::new(800));
+
+    let auto_compound = ExecuteMsg::AutoCompound {
+        staker: ALICE.to_string(),
+    };
+    let err = suite.execute(BOB, &auto_compound, &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::AutoCompoundDisabled {}
+    );
+    suite
+        .execute(ALICE, &ExecuteMsg::SetAutoCompound { enabled: true }, &[])
+        .unwrap();
+    let err = suite.execute(BOB, &auto_compound, &[]).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NothingToClaim {}
+    );
+}